@@ -0,0 +1,176 @@
+//! `graphivault-cli`: scriptable vault operations for power users and CI, built on the same
+//! `graphivault-core` library the Tauri app uses so there is exactly one implementation of the
+//! storage/chunking/master-key logic to keep correct.
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use graphivault_core::database::{ImageRecord, TagRecord};
+use graphivault_core::keys;
+use graphivault_core::store::{self, VaultStore};
+use graphivault_core::{chunking, query};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "graphivault-cli", about = "Script GraphiVault vault operations from the command line")]
+struct Cli {
+    /// Vault directory (holding vault.db and the chunk store); created if it doesn't exist.
+    #[arg(long, global = true)]
+    vault_path: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Verify the master password without touching any image.
+    Unlock {
+        #[arg(long)]
+        password: String,
+    },
+    /// Encrypt and register a file in the vault.
+    Add {
+        file: PathBuf,
+        #[arg(long = "tags", value_delimiter = ',')]
+        tags: Vec<String>,
+        #[arg(long)]
+        password: String,
+    },
+    /// List images matching a structured query, e.g. `tag:landscape AND NOT tag:draft`.
+    Search {
+        #[arg(default_value = "")]
+        query: String,
+    },
+    /// Decrypt an image back out to a plaintext file.
+    Export {
+        id: i64,
+        #[arg(long = "out")]
+        out: PathBuf,
+        #[arg(long)]
+        password: String,
+    },
+    /// Print vault metadata.
+    Info,
+}
+
+async fn open_store(vault_path: &std::path::Path) -> Result<Box<dyn VaultStore>> {
+    std::fs::create_dir_all(vault_path)?;
+    let db_path = vault_path.join("vault.db");
+    let database_url = format!(
+        "sqlite:{}?mode=rwc",
+        db_path.to_string_lossy().replace('\\', "/")
+    );
+
+    let store = store::select_store(&database_url, None).await?;
+    store.migrate().await?;
+    Ok(store)
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let store = open_store(&cli.vault_path).await?;
+
+    match cli.command {
+        Command::Unlock { password } => {
+            keys::unlock_vault_key(store.as_ref(), &password)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            println!("vault unlocked");
+        }
+
+        Command::Add {
+            file,
+            tags,
+            password,
+        } => {
+            let key = keys::unlock_vault_key(store.as_ref(), &password)
+                .await
+                .map_err(|e| anyhow!(e))?;
+
+            let plaintext = std::fs::read(&file)?;
+            let file_hash = {
+                use sha2::{Digest, Sha256};
+                hex::encode(Sha256::digest(&plaintext))
+            };
+            let now = chrono::Utc::now().to_rfc3339();
+
+            let image_id = store
+                .insert_image(&ImageRecord {
+                    id: 0,
+                    file_hash,
+                    file_name: file
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("unnamed")
+                        .to_string(),
+                    storage_path: "chunked".to_string(),
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                    file_size: plaintext.len() as i64,
+                    is_deleted: false,
+                    needs_reencryption: false,
+                })
+                .await?;
+
+            chunking::store_image_chunked(store.as_ref(), &cli.vault_path, &key, image_id, &plaintext)
+                .await?;
+
+            for tag_name in tags {
+                store
+                    .insert_tag(&TagRecord {
+                        id: 0,
+                        image_id,
+                        tag_name,
+                        tag_type: None,
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                    })
+                    .await?;
+            }
+
+            println!("added image {}", image_id);
+        }
+
+        Command::Search { query } => {
+            let parsed = query::parse(&query).map_err(|e| anyhow!(e))?;
+            for image in store.search_images(&parsed).await? {
+                println!("{}\t{}\t{}", image.id, image.file_name, image.file_hash);
+            }
+        }
+
+        Command::Export { id, out, password } => {
+            let key = keys::unlock_vault_key(store.as_ref(), &password)
+                .await
+                .map_err(|e| anyhow!(e))?;
+
+            let plaintext =
+                chunking::reassemble_image(store.as_ref(), &cli.vault_path, &key, id).await?;
+            std::fs::write(&out, plaintext)?;
+
+            println!("exported image {} to {}", id, out.display());
+        }
+
+        Command::Info => {
+            let vault_id = store
+                .get_vault_meta("vault_id")
+                .await?
+                .unwrap_or_else(|| "uninitialized".to_string());
+            let created_at = store.get_vault_meta("created_at").await?.unwrap_or_default();
+
+            println!("vault_id: {}", vault_id);
+            println!("created_at: {}", created_at);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+//! Content-defined chunking for the image vault's backing store.
+//!
+//! Images used to be encrypted and written to disk whole, so two images that only differed by a
+//! few edited pixels (or a re-saved duplicate) were stored as two fully independent files. This
+//! module splits each image into variable-size chunks at content-defined boundaries (a Gear-hash
+//! rolling checksum, not fixed offsets), so identical regions across different images land on
+//! identical chunks and are hashed, encrypted and stored exactly once. Each chunk is addressed by
+//! its BLAKE3 hash, encrypted at rest with the vault's master key, and kept under
+//! `vault/chunks/<hash prefix>/<hash>`; [`crate::store::VaultStore`] tracks the `chunks` refcount
+//! table and the per-image chunk sequence in `image_chunks`.
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::keys::KEY_LEN;
+use crate::store::VaultStore;
+
+const MIN_CHUNK_SIZE: usize = 2 * 1024; // 2 KiB
+const MAX_CHUNK_SIZE: usize = 8 * 1024 * 1024; // 8 MiB
+/// Cut a chunk boundary once the rolling hash's low `BOUNDARY_BITS` bits are all zero, which
+/// (past `MIN_CHUNK_SIZE`) happens on average every `2^BOUNDARY_BITS` bytes.
+const BOUNDARY_BITS: u32 = 16;
+const BOUNDARY_MASK: u64 = (1u64 << BOUNDARY_BITS) - 1;
+const NONCE_LEN: usize = 24;
+
+/// Per-byte-value table for the Gear hash, seeded once with a fixed splitmix64 sequence so
+/// chunk boundaries are reproducible across runs and machines.
+fn gear_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u64; 256];
+        let mut seed: u64 = 0x9E3779B97F4A7C15;
+        for slot in table.iter_mut() {
+            seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            *slot = z ^ (z >> 31);
+        }
+        table
+    })
+}
+
+/// Split `data` into content-defined chunks bounded by `MIN_CHUNK_SIZE`/`MAX_CHUNK_SIZE`. A
+/// single-byte insertion or deletion only shifts the chunk boundaries immediately around it,
+/// leaving every other chunk's hash (and therefore its dedup) unaffected.
+fn split_chunks(data: &[u8]) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let table = gear_table();
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+    let mut hash: u64 = 0;
+
+    for (i, &byte) in data.iter().enumerate() {
+        hash = hash.wrapping_shl(1).wrapping_add(table[byte as usize]);
+        let len = i - start + 1;
+
+        if (len >= MIN_CHUNK_SIZE && hash & BOUNDARY_MASK == 0) || len >= MAX_CHUNK_SIZE {
+            chunks.push(&data[start..=i]);
+            start = i + 1;
+            hash = 0;
+        }
+    }
+
+    if start < data.len() {
+        chunks.push(&data[start..]);
+    }
+
+    chunks
+}
+
+fn chunk_path(vault_dir: &Path, hash: &str) -> PathBuf {
+    vault_dir.join("chunks").join(&hash[0..2]).join(hash)
+}
+
+fn write_chunk_file(vault_dir: &Path, key: &[u8; KEY_LEN], hash: &str, chunk: &[u8]) -> Result<()> {
+    let path = chunk_path(vault_dir, hash);
+    std::fs::create_dir_all(path.parent().ok_or_else(|| anyhow!("invalid chunk path"))?)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), chunk)
+        .map_err(|e| anyhow!("failed to encrypt chunk {}: {}", hash, e))?;
+
+    let mut contents = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    contents.extend_from_slice(&nonce_bytes);
+    contents.extend_from_slice(&ciphertext);
+    std::fs::write(path, contents)?;
+
+    Ok(())
+}
+
+fn read_chunk_file(vault_dir: &Path, key: &[u8; KEY_LEN], hash: &str) -> Result<Vec<u8>> {
+    let path = chunk_path(vault_dir, hash);
+    let contents = std::fs::read(&path)?;
+    if contents.len() < NONCE_LEN {
+        return Err(anyhow!("chunk {} is truncated", hash));
+    }
+    let (nonce_bytes, ciphertext) = contents.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|e| anyhow!("failed to decrypt chunk {}: {}", hash, e))
+}
+
+/// Split `plaintext` into content-defined chunks, write any not already in the store, and
+/// record `image_id`'s chunk sequence. Chunks shared with another image are only ref-counted,
+/// never re-written to disk.
+pub async fn store_image_chunked(
+    store: &dyn VaultStore,
+    vault_dir: &Path,
+    key: &[u8; KEY_LEN],
+    image_id: i64,
+    plaintext: &[u8],
+) -> Result<()> {
+    for (seq, chunk) in split_chunks(plaintext).into_iter().enumerate() {
+        let hash = blake3::hash(chunk).to_hex().to_string();
+
+        if store.touch_chunk(&hash, chunk.len() as i64).await? {
+            write_chunk_file(vault_dir, key, &hash, chunk)?;
+        }
+
+        store.link_image_chunk(image_id, seq as i64, &hash).await?;
+    }
+
+    Ok(())
+}
+
+/// Reassemble `image_id`'s original bytes by decrypting and concatenating its chunks in order.
+pub async fn reassemble_image(
+    store: &dyn VaultStore,
+    vault_dir: &Path,
+    key: &[u8; KEY_LEN],
+    image_id: i64,
+) -> Result<Vec<u8>> {
+    let hashes = store.get_image_chunk_hashes(image_id).await?;
+    let mut plaintext = Vec::new();
+
+    for hash in hashes {
+        plaintext.extend(read_chunk_file(vault_dir, key, &hash)?);
+    }
+
+    Ok(plaintext)
+}
+
+/// Unlink `image_id` from its chunks and garbage-collect any chunk left with no remaining
+/// references, removing both its `chunks` row and its file on disk.
+pub async fn delete_image_chunks(store: &dyn VaultStore, vault_dir: &Path, image_id: i64) -> Result<()> {
+    let hashes = store.get_image_chunk_hashes(image_id).await?;
+    store.unlink_image_chunks(image_id).await?;
+
+    for hash in hashes {
+        let remaining = store.decrement_chunk_refcount(&hash).await?;
+        if remaining <= 0 {
+            let _ = std::fs::remove_file(chunk_path(vault_dir, &hash));
+            store.remove_chunk_record(&hash).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ImageRecord;
+    use crate::store::SqliteStore;
+
+    #[test]
+    fn split_chunks_reassembles_to_the_original_bytes() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let chunks = split_chunks(&data);
+
+        assert!(!chunks.is_empty());
+        assert_eq!(chunks.iter().map(|c| c.len()).sum::<usize>(), data.len());
+        assert_eq!(chunks.concat(), data);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.len() >= MIN_CHUNK_SIZE);
+            assert!(chunk.len() <= MAX_CHUNK_SIZE);
+        }
+    }
+
+    #[test]
+    fn split_chunks_of_empty_input_is_empty() {
+        assert!(split_chunks(&[]).is_empty());
+    }
+
+    #[test]
+    fn split_chunks_is_deterministic() {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 97) as u8).collect();
+        assert_eq!(split_chunks(&data), split_chunks(&data));
+    }
+
+    #[test]
+    fn a_single_inserted_byte_only_perturbs_nearby_boundaries() {
+        // Pseudo-random (xorshift32) bytes, so the input has real content-defined boundaries;
+        // a short-period pattern never hits the boundary mask and comes out as one chunk.
+        let mut state: u32 = 0x2545_F491;
+        let mut data: Vec<u8> = (0..1_000_000u32)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state >> 24) as u8
+            })
+            .collect();
+        let original: Vec<Vec<u8>> = split_chunks(&data).into_iter().map(|c| c.to_vec()).collect();
+
+        let edit_at = 200_000;
+        data.insert(edit_at, 0xFF);
+        let edited: Vec<Vec<u8>> = split_chunks(&data).into_iter().map(|c| c.to_vec()).collect();
+
+        // Everything before the edit should still cut identically.
+        let unaffected = original
+            .iter()
+            .scan(0usize, |end, c| {
+                *end += c.len();
+                Some(*end)
+            })
+            .take_while(|&end| end <= edit_at)
+            .count();
+        assert!(unaffected > 0);
+        assert_eq!(&original[..unaffected], &edited[..unaffected]);
+        // And the edit shouldn't have forced every later chunk to change too.
+        assert!(edited.len() >= original.len());
+        assert_eq!(&original[unaffected + 1..], &edited[unaffected + 1..]);
+    }
+
+    async fn open_test_store() -> (SqliteStore, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vault.db");
+        let store = SqliteStore::connect(&format!("sqlite:{}?mode=rwc", db_path.display()))
+            .await
+            .unwrap();
+        store.migrate().await.unwrap();
+        (store, dir)
+    }
+
+    async fn insert_test_image(store: &SqliteStore, file_name: &str) -> i64 {
+        let now = "2024-01-01T00:00:00+00:00".to_string();
+        store
+            .insert_image(&ImageRecord {
+                id: 0,
+                file_hash: format!("hash-{}", file_name),
+                file_name: file_name.to_string(),
+                storage_path: file_name.to_string(),
+                created_at: now.clone(),
+                updated_at: now,
+                file_size: 0,
+                is_deleted: false,
+                needs_reencryption: false,
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn duplicate_images_share_chunks_and_gc_only_once_both_are_deleted() {
+        let (store, dir) = open_test_store().await;
+        let vault_dir = dir.path();
+        let key = [0u8; KEY_LEN];
+        let plaintext: Vec<u8> = (0..50_000u32).map(|i| (i % 211) as u8).collect();
+
+        let image_id_a = insert_test_image(&store, "a.jpg").await;
+        let image_id_b = insert_test_image(&store, "b.jpg").await;
+
+        store_image_chunked(&store, vault_dir, &key, image_id_a, &plaintext).await.unwrap();
+        store_image_chunked(&store, vault_dir, &key, image_id_b, &plaintext).await.unwrap();
+
+        let hashes_a = store.get_image_chunk_hashes(image_id_a).await.unwrap();
+        let hashes_b = store.get_image_chunk_hashes(image_id_b).await.unwrap();
+        assert_eq!(hashes_a, hashes_b);
+        assert!(!hashes_a.is_empty());
+
+        assert_eq!(
+            reassemble_image(&store, vault_dir, &key, image_id_b).await.unwrap(),
+            plaintext
+        );
+
+        // Deleting the first image must not remove chunks the second image still references.
+        delete_image_chunks(&store, vault_dir, image_id_a).await.unwrap();
+        for hash in &hashes_a {
+            assert!(chunk_path(vault_dir, hash).exists());
+        }
+        assert_eq!(
+            reassemble_image(&store, vault_dir, &key, image_id_b).await.unwrap(),
+            plaintext
+        );
+
+        // Once the last reference is gone, the chunk files are actually removed.
+        delete_image_chunks(&store, vault_dir, image_id_b).await.unwrap();
+        for hash in &hashes_a {
+            assert!(!chunk_path(vault_dir, hash).exists());
+        }
+    }
+}
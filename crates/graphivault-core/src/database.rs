@@ -0,0 +1,976 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::query::QueryParam;
+
+// GraphiVault Database Models
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImageRecord {
+    pub id: i64,
+    pub file_hash: String,
+    pub file_name: String,  // Encrypted filename
+    pub storage_path: String,  // Vault-relative path
+    pub created_at: String,
+    pub updated_at: String,
+    pub file_size: i64,
+    pub is_deleted: bool,
+    /// Set for a pre-existing image when a legacy (pre-single-master-key) vault's password is
+    /// adopted without verification (see `keys::adopt_legacy_vault_password`): the image is still
+    /// encrypted under its own old per-file key, not the newly adopted master key, so it can't be
+    /// decrypted until it's re-saved. Always `false` for any image inserted after that point.
+    pub needs_reencryption: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TagRecord {
+    pub id: i64,
+    pub image_id: i64,
+    pub tag_name: String,  // Encrypted tag
+    pub tag_type: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AnnotationRecord {
+    pub id: i64,
+    pub image_id: i64,
+    pub note: String,  // Encrypted note
+    pub created_at: String,
+}
+
+/// Column `list_images` is allowed to sort by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderBy {
+    CreatedAt,
+    UpdatedAt,
+    FileSize,
+    FileName,
+}
+
+impl OrderBy {
+    fn column(self) -> &'static str {
+        match self {
+            OrderBy::CreatedAt => "created_at",
+            OrderBy::UpdatedAt => "updated_at",
+            OrderBy::FileSize => "file_size",
+            OrderBy::FileName => "file_name",
+        }
+    }
+}
+
+/// Parameters for a paginated, sortable, filterable image listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageQuery {
+    pub order_by: OrderBy,
+    pub ascending: bool,
+    pub min_file_size: Option<i64>,
+    pub max_file_size: Option<i64>,
+    /// Row offset to resume from; `None` starts at the first page.
+    pub cursor: Option<i64>,
+    pub page_size: i64,
+}
+
+impl Default for ImageQuery {
+    fn default() -> Self {
+        Self {
+            order_by: OrderBy::CreatedAt,
+            ascending: false,
+            min_file_size: None,
+            max_file_size: None,
+            cursor: None,
+            page_size: 50,
+        }
+    }
+}
+
+/// One page of `list_images` results, plus enough information to fetch the next page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexResult {
+    pub images: Vec<ImageRecord>,
+    pub total_count: i64,
+    pub next_cursor: Option<i64>,
+}
+
+/// A pre-rendered derived image (thumbnail, preview, ...) for one `image_id`/`preset_name` pair.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImageVariantRecord {
+    pub id: i64,
+    pub image_id: i64,
+    pub preset_name: String,
+    pub format: String, // webp | png | jpeg | avif
+    pub width: i64,
+    pub height: i64,
+    pub storage_path: String,
+    pub byte_size: i64,
+    pub created_at: String,
+}
+
+/// A named rendition GraphiVault should keep pre-generated for every image, e.g. `thumb` at
+/// 256px WebP. Stored as JSON under the `variant_presets` key in `vault_meta`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantPreset {
+    pub name: String,
+    pub format: String,
+    pub max_dimension: u32,
+}
+
+fn default_variant_presets() -> Vec<VariantPreset> {
+    vec![
+        VariantPreset {
+            name: "thumb".to_string(),
+            format: "webp".to_string(),
+            max_dimension: 256,
+        },
+        VariantPreset {
+            name: "preview".to_string(),
+            format: "webp".to_string(),
+            max_dimension: 1024,
+        },
+    ]
+}
+
+/// A capability token minted via `capability::grant_capability`, tracked so it can be listed and
+/// revoked by `token_id` without decoding the token itself.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IssuedTokenRecord {
+    pub token_id: String,
+    pub resource_kind: String,  // images | tags
+    pub resource_value: String, // comma-separated image ids or tag names
+    pub ops: String,            // comma-separated permitted operations
+    pub created_at: String,
+    pub expires_at: String,
+    pub revoked: bool,
+}
+
+/// A row copied out of `tags` by the `tags_history` triggers before an update or delete
+/// overwrote it, so an accidentally removed tag can be recovered.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TagHistoryRecord {
+    pub id: i64,
+    pub tag_id: i64,
+    pub image_id: i64,
+    pub tag_name: String,
+    pub tag_type: Option<String>,
+    pub created_at: String,
+    pub op: String,
+    pub changed_at: String,
+}
+
+/// Connect to (and create, if missing) the SQLite database file at `database_url`.
+///
+/// This only opens the pool; schema creation lives in [`migrate_sqlite`] so that it can be
+/// invoked through the `VaultStore::migrate` trait method instead of being tied to startup.
+pub async fn connect(database_url: &str) -> Result<SqlitePool> {
+    let pool = SqlitePool::connect(database_url).await?;
+
+    // Configure SQLite for optimal security and performance
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&pool).await?;
+    sqlx::query("PRAGMA journal_mode = WAL").execute(&pool).await?;
+    sqlx::query("PRAGMA synchronous = NORMAL").execute(&pool).await?;
+    sqlx::query("PRAGMA secure_delete = ON").execute(&pool).await?;
+    sqlx::query("PRAGMA auto_vacuum = INCREMENTAL").execute(&pool).await?;
+    sqlx::query("PRAGMA page_size = 4096").execute(&pool).await?;
+    sqlx::query("PRAGMA cache_size = -64000").execute(&pool).await?; // 64MB cache
+    sqlx::query("PRAGMA temp_store = MEMORY").execute(&pool).await?;
+
+    Ok(pool)
+}
+
+/// One forward-only schema change, applied atomically and recorded once `up_sql` has run.
+pub struct Migration {
+    pub version: u32,
+    pub up_sql: &'static str,
+}
+
+/// All migrations this binary knows about, in ascending version order. Add new ones by
+/// appending a new version here and a new `.sql` file under `migrations/` — never edit an
+/// already-shipped entry, since vaults that already applied it would silently diverge.
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            up_sql: include_str!("migrations/0001_initial_schema.sql"),
+        },
+        Migration {
+            version: 2,
+            up_sql: include_str!("migrations/0002_image_variants.sql"),
+        },
+        Migration {
+            version: 3,
+            up_sql: include_str!("migrations/0003_chunk_store.sql"),
+        },
+        Migration {
+            version: 4,
+            up_sql: include_str!("migrations/0004_issued_tokens.sql"),
+        },
+        Migration {
+            version: 5,
+            up_sql: include_str!("migrations/0005_needs_reencryption.sql"),
+        },
+    ]
+}
+
+fn checksum(sql: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(sql.as_bytes()))
+}
+
+/// Split a migration script into its top-level statements, treating `BEGIN ... END` (a trigger
+/// body) as a single statement rather than splitting on the semicolons inside it, and ignoring
+/// anything inside a `--` line comment (a semicolon in prose there isn't a statement boundary).
+fn split_statements(script: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut depth: u32 = 0;
+    let mut start = 0usize;
+    let bytes = script.as_bytes();
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        let rest = &script[i..];
+        if rest.starts_with("--") {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if depth == 0 && bytes[i] == b';' {
+            let statement = script[start..=i].trim();
+            if !statement.is_empty() {
+                statements.push(statement);
+            }
+            start = i + 1;
+        } else if rest.len() >= 5 && rest[..5].eq_ignore_ascii_case("BEGIN") {
+            depth += 1;
+        } else if rest.len() >= 3 && rest[..3].eq_ignore_ascii_case("END") {
+            depth = depth.saturating_sub(1);
+        }
+        i += 1;
+    }
+
+    let trailing = script[start..].trim();
+    if !trailing.is_empty() {
+        statements.push(trailing);
+    }
+
+    statements
+}
+
+/// Bring the database up to the newest schema this binary knows about.
+///
+/// `vault_meta.schema_version` is the authoritative version; `migrations` is the audit trail
+/// (version, applied_at, checksum) recorded alongside each one applied. Only pending migrations
+/// run, each inside its own transaction, and the stored version is bumped atomically with it.
+/// Refuses to start if the stored version is newer than any migration in `migrations()`, since
+/// that means a newer build already touched this vault. Also refuses to start if an
+/// already-applied migration's checksum no longer matches the `.sql` file shipped in this binary,
+/// since that means an already-shipped migration was edited after some vault applied it — exactly
+/// the kind of divergence `migrations()`'s "never edit an already-shipped entry" rule exists to
+/// prevent, and is cheaper to catch here than to debug from a corrupted vault later.
+///
+/// This hand-rolled runner (rather than `sqlx::migrate!` over a `migrations/` directory) predates
+/// this file being its own crate and already covers what that macro would buy us — versioned,
+/// forward-only, transactional upgrades with an audit trail — while staying in control of the
+/// `vault_meta.schema_version` bookkeeping and first-run seeding below, which `sqlx::migrate!`
+/// doesn't know about.
+pub async fn migrate_sqlite(pool: &SqlitePool) -> Result<()> {
+    // vault_meta tracks the authoritative schema_version and must exist before any numbered
+    // migration can run; migrations is the audit trail alongside it.
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS vault_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            last_updated TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL,
+            checksum TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let migrations = migrations();
+    let latest_known = migrations.iter().map(|m| m.version).max().unwrap_or(0);
+
+    let current_version: u32 = get_vault_meta(pool, "schema_version")
+        .await?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    if current_version > latest_known {
+        return Err(anyhow::anyhow!(
+            "vault schema_version {} is newer than this binary supports (up to {}); refusing to start",
+            current_version,
+            latest_known
+        ));
+    }
+
+    let applied_checksums: Vec<(u32, String)> =
+        sqlx::query_as("SELECT version, checksum FROM migrations")
+            .fetch_all(pool)
+            .await?;
+
+    for (version, applied_checksum) in &applied_checksums {
+        if let Some(migration) = migrations.iter().find(|m| m.version == *version) {
+            let expected = checksum(migration.up_sql);
+            if &expected != applied_checksum {
+                return Err(anyhow::anyhow!(
+                    "migration {} has been modified since it was applied to this vault; refusing to start",
+                    version
+                ));
+            }
+        }
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current_version) {
+        let mut tx = pool.begin().await?;
+
+        // Each migration file is a script of semicolon-separated statements, including
+        // multi-statement `CREATE TRIGGER ... BEGIN ... END` blocks; `split_statements` keeps
+        // those intact instead of splitting on the semicolons inside them.
+        for statement in split_statements(migration.up_sql) {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query("INSERT OR REPLACE INTO vault_meta (key, value, last_updated) VALUES ('schema_version', ?, ?)")
+            .bind(migration.version.to_string())
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("INSERT INTO migrations (version, applied_at, checksum) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(&now)
+            .bind(checksum(migration.up_sql))
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+    }
+
+    // Seed the vault's own identity the first time it's opened (not schema, so not a migration)
+    if get_vault_meta(pool, "vault_id").await?.is_none() {
+        let now = Utc::now().to_rfc3339();
+        let vault_id = uuid::Uuid::new_v4().to_string();
+
+        sqlx::query("INSERT INTO vault_meta (key, value, last_updated) VALUES (?, ?, ?)")
+            .bind("vault_id")
+            .bind(&vault_id)
+            .bind(&now)
+            .execute(pool)
+            .await?;
+
+        sqlx::query("INSERT INTO vault_meta (key, value, last_updated) VALUES (?, ?, ?)")
+            .bind("created_at")
+            .bind(&now)
+            .bind(&now)
+            .execute(pool)
+            .await?;
+    }
+
+    if get_vault_meta(pool, "variant_presets").await?.is_none() {
+        let now = Utc::now().to_rfc3339();
+        let presets_json = serde_json::to_string(&default_variant_presets())?;
+
+        sqlx::query("INSERT INTO vault_meta (key, value, last_updated) VALUES (?, ?, ?)")
+            .bind("variant_presets")
+            .bind(&presets_json)
+            .bind(&now)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn insert_image(pool: &SqlitePool, image: &ImageRecord) -> Result<i64> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO images (
+            file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted,
+            needs_reencryption
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&image.file_hash)
+    .bind(&image.file_name)
+    .bind(&image.storage_path)
+    .bind(&image.created_at)
+    .bind(&image.updated_at)
+    .bind(image.file_size)
+    .bind(image.is_deleted)
+    .bind(image.needs_reencryption)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Flag every non-deleted image as needing re-encryption under a newly adopted master key.
+/// Used only by [`crate::keys::adopt_legacy_vault_password`], when a vault predating the
+/// single-master-key scheme has its password "adopted" without any way to verify it against the
+/// images' original per-file keys — those images stay undecryptable until re-saved, and this
+/// flag is what lets the frontend warn about that instead of just failing silently on open.
+pub async fn mark_all_images_needing_reencryption(pool: &SqlitePool) -> Result<()> {
+    sqlx::query("UPDATE images SET needs_reencryption = 1 WHERE is_deleted = 0")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_all_images(pool: &SqlitePool) -> Result<Vec<ImageRecord>> {
+    let images = sqlx::query_as::<_, ImageRecord>(
+        "SELECT id, file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted, needs_reencryption FROM images WHERE is_deleted = 0 ORDER BY created_at DESC"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(images)
+}
+
+/// Page through non-deleted images, sorted and filtered per `query`.
+///
+/// Reuses `idx_images_created_at`/`idx_images_updated_at`/`idx_images_file_size` for the
+/// `ORDER BY`/range filter (ordering by `file_name` has no index), and pages via `LIMIT`/`OFFSET`
+/// using `query.cursor` as the starting offset.
+pub async fn list_images(pool: &SqlitePool, query: &ImageQuery) -> Result<IndexResult> {
+    let offset = query.cursor.unwrap_or(0);
+    let direction = if query.ascending { "ASC" } else { "DESC" };
+    let column = query.order_by.column();
+
+    let mut where_clauses = vec!["is_deleted = 0".to_string()];
+    if query.min_file_size.is_some() {
+        where_clauses.push("file_size >= ?".to_string());
+    }
+    if query.max_file_size.is_some() {
+        where_clauses.push("file_size <= ?".to_string());
+    }
+    let where_sql = where_clauses.join(" AND ");
+
+    let count_sql = format!("SELECT COUNT(*) FROM images WHERE {}", where_sql);
+    let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql);
+    if let Some(min) = query.min_file_size {
+        count_query = count_query.bind(min);
+    }
+    if let Some(max) = query.max_file_size {
+        count_query = count_query.bind(max);
+    }
+    let (total_count,) = count_query.fetch_one(pool).await?;
+
+    let select_sql = format!(
+        "SELECT id, file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted, needs_reencryption \
+         FROM images WHERE {} ORDER BY {} {} LIMIT ? OFFSET ?",
+        where_sql, column, direction
+    );
+    let mut select_query = sqlx::query_as::<_, ImageRecord>(&select_sql);
+    if let Some(min) = query.min_file_size {
+        select_query = select_query.bind(min);
+    }
+    if let Some(max) = query.max_file_size {
+        select_query = select_query.bind(max);
+    }
+    let images = select_query
+        .bind(query.page_size)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    let next_cursor = if offset + (images.len() as i64) < total_count {
+        Some(offset + images.len() as i64)
+    } else {
+        None
+    };
+
+    Ok(IndexResult {
+        images,
+        total_count,
+        next_cursor,
+    })
+}
+
+/// Run a compiled [`crate::query::Query`] (see `query::compile`) against `images`/`tags`,
+/// binding `params` to the `WHERE` clause's `?` placeholders in order.
+pub async fn search_images(
+    pool: &SqlitePool,
+    where_sql: &str,
+    params: &[QueryParam],
+) -> Result<Vec<ImageRecord>> {
+    let sql = format!(
+        "SELECT id, file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted, needs_reencryption \
+         FROM images WHERE is_deleted = 0 AND ({}) ORDER BY created_at DESC",
+        where_sql
+    );
+
+    let mut query = sqlx::query_as::<_, ImageRecord>(&sql);
+    for param in params {
+        query = match param {
+            QueryParam::Text(s) => query.bind(s),
+            QueryParam::Int(i) => query.bind(i),
+        };
+    }
+
+    Ok(query.fetch_all(pool).await?)
+}
+
+pub async fn get_image_by_id(pool: &SqlitePool, id: i64) -> Result<Option<ImageRecord>> {
+    let image = sqlx::query_as::<_, ImageRecord>(
+        "SELECT id, file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted, needs_reencryption FROM images WHERE id = ? AND is_deleted = 0"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(image)
+}
+
+pub async fn get_image_by_hash(pool: &SqlitePool, file_hash: &str) -> Result<Option<ImageRecord>> {
+    let image = sqlx::query_as::<_, ImageRecord>(
+        "SELECT id, file_hash, file_name, storage_path, created_at, updated_at, file_size, is_deleted, needs_reencryption FROM images WHERE file_hash = ? AND is_deleted = 0"
+    )
+    .bind(file_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(image)
+}
+
+pub async fn soft_delete_image(pool: &SqlitePool, id: i64) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query("UPDATE images SET is_deleted = 1, updated_at = ? WHERE id = ?")
+        .bind(&now)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn insert_tag(pool: &SqlitePool, tag: &TagRecord) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO tags (image_id, tag_name, tag_type, created_at) VALUES (?, ?, ?, ?)"
+    )
+    .bind(tag.image_id)
+    .bind(&tag.tag_name)
+    .bind(&tag.tag_type)
+    .bind(&tag.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_image_tags(pool: &SqlitePool, image_id: i64) -> Result<Vec<TagRecord>> {
+    let tags = sqlx::query_as::<_, TagRecord>(
+        "SELECT id, image_id, tag_name, tag_type, created_at FROM tags WHERE image_id = ? ORDER BY created_at"
+    )
+    .bind(image_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+/// Every recorded update/delete made to `image_id`'s tags, most recent first.
+pub async fn get_tag_history(pool: &SqlitePool, image_id: i64) -> Result<Vec<TagHistoryRecord>> {
+    let history = sqlx::query_as::<_, TagHistoryRecord>(
+        "SELECT id, tag_id, image_id, tag_name, tag_type, created_at, op, changed_at \
+         FROM tags_history WHERE image_id = ? ORDER BY changed_at DESC",
+    )
+    .bind(image_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(history)
+}
+
+/// Recreate a tag from a `tags_history` row (as a new row, not an in-place undo, since the
+/// original may have been deleted entirely).
+pub async fn restore_tag(pool: &SqlitePool, history_id: i64) -> Result<i64> {
+    let history: TagHistoryRecord = sqlx::query_as(
+        "SELECT id, tag_id, image_id, tag_name, tag_type, created_at, op, changed_at \
+         FROM tags_history WHERE id = ?",
+    )
+    .bind(history_id)
+    .fetch_one(pool)
+    .await?;
+
+    insert_tag(
+        pool,
+        &TagRecord {
+            id: 0,
+            image_id: history.image_id,
+            tag_name: history.tag_name,
+            tag_type: history.tag_type,
+            created_at: history.created_at,
+        },
+    )
+    .await
+}
+
+pub async fn insert_annotation(pool: &SqlitePool, annotation: &AnnotationRecord) -> Result<i64> {
+    let result = sqlx::query(
+        "INSERT INTO annotations (image_id, note, created_at) VALUES (?, ?, ?)"
+    )
+    .bind(annotation.image_id)
+    .bind(&annotation.note)
+    .bind(&annotation.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_image_annotations(pool: &SqlitePool, image_id: i64) -> Result<Vec<AnnotationRecord>> {
+    let annotations = sqlx::query_as::<_, AnnotationRecord>(
+        "SELECT id, image_id, note, created_at FROM annotations WHERE image_id = ? ORDER BY created_at"
+    )
+    .bind(image_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(annotations)
+}
+
+pub async fn set_vault_meta(pool: &SqlitePool, key: &str, value: &str) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query("INSERT OR REPLACE INTO vault_meta (key, value, last_updated) VALUES (?, ?, ?)")
+        .bind(key)
+        .bind(value)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_vault_meta(pool: &SqlitePool, key: &str) -> Result<Option<String>> {
+    let result: Option<(String,)> = sqlx::query_as("SELECT value FROM vault_meta WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(result.map(|r| r.0))
+}
+
+/// Register (or replace) the rendition for `variant.image_id`/`variant.preset_name`, e.g. after
+/// generating it on import or regenerating it because the source `file_hash` changed.
+pub async fn insert_variant(pool: &SqlitePool, variant: &ImageVariantRecord) -> Result<i64> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO image_variants (
+            image_id, preset_name, format, width, height, storage_path, byte_size, created_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(image_id, preset_name) DO UPDATE SET
+            format = excluded.format,
+            width = excluded.width,
+            height = excluded.height,
+            storage_path = excluded.storage_path,
+            byte_size = excluded.byte_size,
+            created_at = excluded.created_at
+        "#,
+    )
+    .bind(variant.image_id)
+    .bind(&variant.preset_name)
+    .bind(&variant.format)
+    .bind(variant.width)
+    .bind(variant.height)
+    .bind(&variant.storage_path)
+    .bind(variant.byte_size)
+    .bind(&variant.created_at)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() > 0 && result.last_insert_rowid() > 0 {
+        Ok(result.last_insert_rowid())
+    } else {
+        let existing = get_variant(pool, variant.image_id, &variant.preset_name)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("variant not found after insert"))?;
+        Ok(existing.id)
+    }
+}
+
+pub async fn get_variants(pool: &SqlitePool, image_id: i64) -> Result<Vec<ImageVariantRecord>> {
+    let variants = sqlx::query_as::<_, ImageVariantRecord>(
+        "SELECT id, image_id, preset_name, format, width, height, storage_path, byte_size, created_at \
+         FROM image_variants WHERE image_id = ? ORDER BY preset_name",
+    )
+    .bind(image_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(variants)
+}
+
+pub async fn get_variant(
+    pool: &SqlitePool,
+    image_id: i64,
+    preset_name: &str,
+) -> Result<Option<ImageVariantRecord>> {
+    let variant = sqlx::query_as::<_, ImageVariantRecord>(
+        "SELECT id, image_id, preset_name, format, width, height, storage_path, byte_size, created_at \
+         FROM image_variants WHERE image_id = ? AND preset_name = ?",
+    )
+    .bind(image_id)
+    .bind(preset_name)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(variant)
+}
+
+/// The named presets (`thumb`, `preview`, ...) GraphiVault keeps pre-generated for every image.
+pub async fn get_variant_presets(pool: &SqlitePool) -> Result<Vec<VariantPreset>> {
+    match get_vault_meta(pool, "variant_presets").await? {
+        Some(json) => Ok(serde_json::from_str(&json).unwrap_or_else(|_| default_variant_presets())),
+        None => Ok(default_variant_presets()),
+    }
+}
+
+/// Record `hash` in the chunk store if it hasn't been seen before, or bump its refcount if some
+/// other image already contains an identical chunk. Returns `true` only the first time, telling
+/// the caller whether it still needs to write the chunk's encrypted contents to disk.
+pub async fn touch_chunk(pool: &SqlitePool, hash: &str, size: i64) -> Result<bool> {
+    let existing: Option<(i64,)> = sqlx::query_as("SELECT refcount FROM chunks WHERE hash = ?")
+        .bind(hash)
+        .fetch_optional(pool)
+        .await?;
+
+    match existing {
+        Some(_) => {
+            sqlx::query("UPDATE chunks SET refcount = refcount + 1 WHERE hash = ?")
+                .bind(hash)
+                .execute(pool)
+                .await?;
+            Ok(false)
+        }
+        None => {
+            sqlx::query("INSERT INTO chunks (hash, refcount, size) VALUES (?, 1, ?)")
+                .bind(hash)
+                .bind(size)
+                .execute(pool)
+                .await?;
+            Ok(true)
+        }
+    }
+}
+
+pub async fn link_image_chunk(pool: &SqlitePool, image_id: i64, seq: i64, chunk_hash: &str) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO image_chunks (image_id, seq, chunk_hash) VALUES (?, ?, ?)")
+        .bind(image_id)
+        .bind(seq)
+        .bind(chunk_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// The chunk hashes making up `image_id`, in the order they must be concatenated to reassemble
+/// the original file.
+pub async fn get_image_chunk_hashes(pool: &SqlitePool, image_id: i64) -> Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT chunk_hash FROM image_chunks WHERE image_id = ? ORDER BY seq",
+    )
+    .bind(image_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
+
+pub async fn unlink_image_chunks(pool: &SqlitePool, image_id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM image_chunks WHERE image_id = ?")
+        .bind(image_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Drop one reference to `hash` and return its refcount afterward, so the caller can decide
+/// whether the chunk is now orphaned and should be garbage-collected from disk.
+pub async fn decrement_chunk_refcount(pool: &SqlitePool, hash: &str) -> Result<i64> {
+    sqlx::query("UPDATE chunks SET refcount = refcount - 1 WHERE hash = ?")
+        .bind(hash)
+        .execute(pool)
+        .await?;
+
+    let (refcount,): (i64,) = sqlx::query_as("SELECT refcount FROM chunks WHERE hash = ?")
+        .bind(hash)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(refcount)
+}
+
+pub async fn remove_chunk_record(pool: &SqlitePool, hash: &str) -> Result<()> {
+    sqlx::query("DELETE FROM chunks WHERE hash = ?")
+        .bind(hash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn insert_issued_token(
+    pool: &SqlitePool,
+    token_id: &str,
+    resource_kind: &str,
+    resource_value: &str,
+    ops: &str,
+    expires_at: &str,
+) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        "INSERT INTO issued_tokens (token_id, resource_kind, resource_value, ops, created_at, expires_at, revoked) \
+         VALUES (?, ?, ?, ?, ?, ?, 0)",
+    )
+    .bind(token_id)
+    .bind(resource_kind)
+    .bind(resource_value)
+    .bind(ops)
+    .bind(&now)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn list_issued_tokens(pool: &SqlitePool) -> Result<Vec<IssuedTokenRecord>> {
+    let tokens = sqlx::query_as::<_, IssuedTokenRecord>(
+        "SELECT token_id, resource_kind, resource_value, ops, created_at, expires_at, revoked \
+         FROM issued_tokens ORDER BY created_at DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tokens)
+}
+
+pub async fn get_issued_token(pool: &SqlitePool, token_id: &str) -> Result<Option<IssuedTokenRecord>> {
+    let token = sqlx::query_as::<_, IssuedTokenRecord>(
+        "SELECT token_id, resource_kind, resource_value, ops, created_at, expires_at, revoked \
+         FROM issued_tokens WHERE token_id = ?",
+    )
+    .bind(token_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(token)
+}
+
+pub async fn revoke_issued_token(pool: &SqlitePool, token_id: &str) -> Result<()> {
+    sqlx::query("UPDATE issued_tokens SET revoked = 1 WHERE token_id = ?")
+        .bind(token_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn log_auth_event(pool: &SqlitePool, event_type: &str, status: &str, details: Option<&str>) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query("INSERT INTO auth_logs (event_type, timestamp, status, details) VALUES (?, ?, ?, ?)")
+        .bind(event_type)
+        .bind(&now)
+        .bind(status)
+        .bind(details)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn open_test_pool() -> (SqlitePool, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vault.db");
+        let pool = connect(&format!("sqlite:{}?mode=rwc", db_path.display()))
+            .await
+            .unwrap();
+        (pool, dir)
+    }
+
+    #[tokio::test]
+    async fn migrate_seeds_schema_version_and_vault_identity() {
+        let (pool, _dir) = open_test_pool().await;
+        migrate_sqlite(&pool).await.unwrap();
+
+        let latest = migrations().iter().map(|m| m.version).max().unwrap();
+        let schema_version: u32 = get_vault_meta(&pool, "schema_version")
+            .await
+            .unwrap()
+            .and_then(|v| v.parse().ok())
+            .unwrap();
+        assert_eq!(schema_version, latest);
+
+        assert!(get_vault_meta(&pool, "vault_id").await.unwrap().is_some());
+        assert!(get_vault_meta(&pool, "created_at").await.unwrap().is_some());
+        assert!(get_vault_meta(&pool, "variant_presets").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn migrate_is_idempotent() {
+        let (pool, _dir) = open_test_pool().await;
+        migrate_sqlite(&pool).await.unwrap();
+        let vault_id_before = get_vault_meta(&pool, "vault_id").await.unwrap();
+
+        migrate_sqlite(&pool).await.unwrap();
+        let vault_id_after = get_vault_meta(&pool, "vault_id").await.unwrap();
+
+        assert_eq!(vault_id_before, vault_id_after);
+    }
+
+    #[tokio::test]
+    async fn migrate_rejects_a_schema_version_newer_than_this_binary_supports() {
+        let (pool, _dir) = open_test_pool().await;
+        migrate_sqlite(&pool).await.unwrap();
+
+        let latest = migrations().iter().map(|m| m.version).max().unwrap();
+        set_vault_meta(&pool, "schema_version", &(latest + 1).to_string())
+            .await
+            .unwrap();
+
+        let err = migrate_sqlite(&pool).await.unwrap_err();
+        assert!(err.to_string().contains("newer than this binary supports"));
+    }
+
+    #[tokio::test]
+    async fn migrate_rejects_a_tampered_migration_checksum() {
+        let (pool, _dir) = open_test_pool().await;
+        migrate_sqlite(&pool).await.unwrap();
+
+        sqlx::query("UPDATE migrations SET checksum = 'tampered' WHERE version = 1")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = migrate_sqlite(&pool).await.unwrap_err();
+        assert!(err.to_string().contains("has been modified since it was applied"));
+    }
+}
@@ -0,0 +1,51 @@
+//! Whole-file encryption for the simple (non-chunked) `encrypt_file`/`decrypt_file` commands.
+//!
+//! This used to shell out to the Python IPC gateway, passing the vault master key as a `--key`
+//! argv argument — readable by any other local process via `ps`/`/proc/<pid>/cmdline`, which leaks
+//! the one key that decrypts the entire vault. Do the AEAD natively instead, the same
+//! nonce-prefixed XChaCha20-Poly1305 scheme [`crate::chunking`] already uses, so the key never
+//! leaves this process.
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+const NONCE_LEN: usize = 24;
+
+pub async fn encrypt_file(file_path: &str, key: &[u8]) -> Result<String> {
+    let plaintext = std::fs::read(file_path)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), plaintext.as_slice())
+        .map_err(|e| anyhow!("failed to encrypt {}: {}", file_path, e))?;
+
+    let mut contents = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    contents.extend_from_slice(&nonce_bytes);
+    contents.extend_from_slice(&ciphertext);
+
+    let encrypted_path = format!("{}.enc", file_path);
+    std::fs::write(&encrypted_path, contents)?;
+
+    Ok(encrypted_path)
+}
+
+pub async fn decrypt_file(encrypted_file_path: &str, key: &[u8], output_path: &str) -> Result<()> {
+    let contents = std::fs::read(encrypted_file_path)?;
+    if contents.len() < NONCE_LEN {
+        return Err(anyhow!("{} is truncated", encrypted_file_path));
+    }
+    let (nonce_bytes, ciphertext) = contents.split_at(NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|e| anyhow!("failed to decrypt {}: {}", encrypted_file_path, e))?;
+
+    std::fs::write(output_path, plaintext)?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! A typed error for the vault's public-facing boundary (Tauri commands, the CLI's exit path).
+//!
+//! Everything inside this crate keeps returning `anyhow::Result` as before — that convention is
+//! fine for code that only ever gets `.to_string()`'d into a log or a terminal. The Tauri IPC
+//! boundary is different: the frontend needs to tell "wrong password" apart from "vault locked"
+//! apart from "corrupt chunk" so it can branch on *what* went wrong, not just print a sentence.
+//! `VaultError` carries that distinction, plus whatever file path / image id / backend method was
+//! involved, and serializes to `{ code, message, context }` so the frontend can match on `code`
+//! and fall back to `message` for anything it doesn't special-case.
+
+use serde::Serialize;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VaultError {
+    #[error("authentication failed")]
+    AuthFailed,
+
+    #[error("vault is locked")]
+    VaultLocked,
+
+    #[error(
+        "this vault predates single-password protection and already holds images; its password \
+         cannot be verified automatically and must be adopted explicitly"
+    )]
+    LegacyVaultUnverified,
+
+    #[error("image {0} not found")]
+    ImageNotFound(i64),
+
+    #[error("{detail} ({path})")]
+    CorruptData { path: String, detail: String },
+
+    #[error("{method} failed: {stderr}")]
+    Backend { method: String, stderr: String },
+
+    #[error(transparent)]
+    InvalidQuery(#[from] crate::query::ParseError),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+/// `VaultStore`/`keys`/`chunking` all return `anyhow::Result`, so a `sqlx::Error` normally reaches
+/// here already erased into an opaque `anyhow::Error`. None of those call sites wrap it with
+/// `.context(...)`, though, so the original `sqlx::Error` is still recoverable as the root of the
+/// chain; pull it back out here so database failures keep their own `database_error` code instead
+/// of collapsing into `internal_error` along with everything else.
+impl From<anyhow::Error> for VaultError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<sqlx::Error>() {
+            Ok(sqlx_err) => VaultError::Database(sqlx_err),
+            Err(err) => VaultError::Other(err),
+        }
+    }
+}
+
+/// `AuthError::LegacyVaultUnverified` is the only variant the frontend needs to branch on
+/// differently from a plain wrong password; everything else (`WrongPassword`, `NotInitialized`,
+/// `AlreadyInitialized`) already reads as "authentication failed" to the caller.
+impl From<crate::keys::AuthError> for VaultError {
+    fn from(err: crate::keys::AuthError) -> Self {
+        match err {
+            crate::keys::AuthError::LegacyVaultUnverified => VaultError::LegacyVaultUnverified,
+            _ => VaultError::AuthFailed,
+        }
+    }
+}
+
+impl VaultError {
+    /// A stable, machine-readable tag the frontend can switch on instead of pattern-matching the
+    /// message text.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VaultError::AuthFailed => "auth_failed",
+            VaultError::VaultLocked => "vault_locked",
+            VaultError::LegacyVaultUnverified => "legacy_vault_unverified",
+            VaultError::ImageNotFound(_) => "image_not_found",
+            VaultError::CorruptData { .. } => "corrupt_data",
+            VaultError::Backend { .. } => "backend_error",
+            VaultError::InvalidQuery(_) => "invalid_query",
+            VaultError::Database(_) => "database_error",
+            VaultError::Other(_) => "internal_error",
+        }
+    }
+
+    /// Structured detail for whichever file, image id, or backend method was involved, so the
+    /// frontend doesn't have to scrape it back out of `message`.
+    pub fn context(&self) -> serde_json::Value {
+        match self {
+            VaultError::ImageNotFound(id) => serde_json::json!({ "image_id": id }),
+            VaultError::CorruptData { path, detail } => {
+                serde_json::json!({ "path": path, "detail": detail })
+            }
+            VaultError::Backend { method, stderr } => {
+                serde_json::json!({ "method": method, "stderr": stderr })
+            }
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    code: String,
+    message: String,
+    context: serde_json::Value,
+}
+
+impl Serialize for VaultError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        ErrorResponse {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            context: self.context(),
+        }
+        .serialize(serializer)
+    }
+}
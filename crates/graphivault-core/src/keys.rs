@@ -0,0 +1,234 @@
+//! Vault-wide master key management.
+//!
+//! `unlock_vault`/`encrypt_file`/`decrypt_file` used to take a raw password on every call and
+//! hand it straight to the Python backend, so there was no way to validate a password without
+//! attempting a full file decrypt. Instead, `initialize_vault_key` derives one master key via
+//! Argon2id from the password and a random salt, then encrypts a small known sentinel with
+//! XChaCha20-Poly1305 and stores `kdf_salt`/`verify_nonce`/`verify_blob` in `vault_meta`.
+//! `unlock_vault_key` re-derives the key from the entered password and the stored salt and
+//! tries to decrypt `verify_blob`: success proves the password is correct and yields the master
+//! key, failure returns `AuthError::WrongPassword` without touching a single image file. Every
+//! per-image encryption then reuses this one derived key (with its own per-file nonce) instead
+//! of re-running Argon2id for each file.
+//!
+//! `migrate_legacy_vault_key` is what `unlock_vault` actually calls: it unlocks normally if
+//! `kdf_salt` exists, and otherwise only auto-initializes when the vault has no images yet (a
+//! genuinely new vault). A vault with images but no `kdf_salt` predates this scheme entirely —
+//! there's no prior salt/nonce to check a typed password against — so it fails with
+//! `AuthError::LegacyVaultUnverified` rather than silently accepting whatever was typed.
+//! `adopt_legacy_vault_password` is the explicit, separate escape hatch for that case.
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use tokio::sync::RwLock;
+
+use crate::store::VaultStore;
+
+pub const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const VERIFY_SENTINEL: &[u8] = b"graphivault-verify-v1";
+
+const META_SALT: &str = "kdf_salt";
+const META_VERIFY_NONCE: &str = "verify_nonce";
+const META_VERIFY_BLOB: &str = "verify_blob";
+
+#[derive(Debug)]
+pub enum AuthError {
+    NotInitialized,
+    AlreadyInitialized,
+    WrongPassword,
+    /// A vault that predates `kdf_salt` still holds images (so it can't be telling apart from a
+    /// tampered-with one), and there is no prior salt/nonce to verify a typed password against.
+    /// Refuses to silently treat the typed password as correct; the caller must go through
+    /// [`adopt_legacy_vault_password`] instead, which makes the "unverified, images may become
+    /// unreadable" tradeoff an explicit, separate action rather than a side effect of unlocking.
+    LegacyVaultUnverified,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::NotInitialized => write!(f, "vault has not been initialized"),
+            AuthError::AlreadyInitialized => write!(f, "vault is already initialized"),
+            AuthError::WrongPassword => write!(f, "incorrect password"),
+            AuthError::LegacyVaultUnverified => write!(
+                f,
+                "this vault predates single-password protection and already holds images; \
+                 its password cannot be verified automatically and must be adopted explicitly"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Holds the master key once `unlock_vault_key` has succeeded; empty while the vault is locked.
+#[derive(Default)]
+pub struct VaultKeyState(pub RwLock<Option<[u8; KEY_LEN]>>);
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+    let mut key = [0u8; KEY_LEN];
+    argon2::Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn seal_verify_blob(key: &[u8; KEY_LEN]) -> Result<([u8; NONCE_LEN], Vec<u8>)> {
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher =
+        XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let blob = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), VERIFY_SENTINEL)
+        .map_err(|e| anyhow!("failed to seal verify blob: {}", e))?;
+
+    Ok((nonce_bytes, blob))
+}
+
+async fn store_verify_material(store: &dyn VaultStore, key: &[u8; KEY_LEN], salt: &[u8]) -> Result<()> {
+    let (nonce_bytes, blob) = seal_verify_blob(key)?;
+
+    store.set_vault_meta(META_SALT, &hex::encode(salt)).await?;
+    store
+        .set_vault_meta(META_VERIFY_NONCE, &hex::encode(nonce_bytes))
+        .await?;
+    store
+        .set_vault_meta(META_VERIFY_BLOB, &hex::encode(&blob))
+        .await?;
+
+    Ok(())
+}
+
+/// Generate a fresh salt, derive the master key from `password`, and seal the verify blob.
+/// Errors with `AuthError::AlreadyInitialized` if this vault already has one.
+pub async fn initialize_vault_key(store: &dyn VaultStore, password: &str) -> Result<[u8; KEY_LEN]> {
+    if store.get_vault_meta(META_SALT).await?.is_some() {
+        return Err(AuthError::AlreadyInitialized.into());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let key = derive_key(password, &salt)?;
+
+    store_verify_material(store, &key, &salt).await?;
+
+    Ok(key)
+}
+
+/// Re-derive the master key from `password` and prove it's correct by decrypting the stored
+/// verify blob. Never touches image files.
+pub async fn unlock_vault_key(
+    store: &dyn VaultStore,
+    password: &str,
+) -> std::result::Result<[u8; KEY_LEN], AuthError> {
+    let salt_hex = store
+        .get_vault_meta(META_SALT)
+        .await
+        .map_err(|_| AuthError::NotInitialized)?
+        .ok_or(AuthError::NotInitialized)?;
+    let nonce_hex = store
+        .get_vault_meta(META_VERIFY_NONCE)
+        .await
+        .map_err(|_| AuthError::NotInitialized)?
+        .ok_or(AuthError::NotInitialized)?;
+    let blob_hex = store
+        .get_vault_meta(META_VERIFY_BLOB)
+        .await
+        .map_err(|_| AuthError::NotInitialized)?
+        .ok_or(AuthError::NotInitialized)?;
+
+    let salt = hex::decode(salt_hex).map_err(|_| AuthError::NotInitialized)?;
+    let nonce_bytes = hex::decode(nonce_hex).map_err(|_| AuthError::NotInitialized)?;
+    let blob = hex::decode(blob_hex).map_err(|_| AuthError::NotInitialized)?;
+
+    let key = derive_key(password, &salt).map_err(|_| AuthError::WrongPassword)?;
+    let cipher =
+        XChaCha20Poly1305::new_from_slice(&key).map_err(|_| AuthError::WrongPassword)?;
+
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(&nonce_bytes), blob.as_ref())
+        .map_err(|_| AuthError::WrongPassword)?;
+
+    if plaintext != VERIFY_SENTINEL {
+        return Err(AuthError::WrongPassword);
+    }
+
+    Ok(key)
+}
+
+/// Re-wrap only the verify blob under a new password-derived key; does not touch any encrypted
+/// image, since those are keyed by the master key itself, not by the password directly.
+pub async fn change_passphrase(
+    store: &dyn VaultStore,
+    old_password: &str,
+    new_password: &str,
+) -> Result<()> {
+    unlock_vault_key(store, old_password)
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let key = derive_key(new_password, &salt)?;
+
+    store_verify_material(store, &key, &salt).await
+}
+
+/// Unlock a vault that may or may not have been initialized yet. A vault with no `kdf_salt` and
+/// no images is genuinely new, so it's safe to initialize verify material from whatever password
+/// was typed, the same as calling `initialize_vault_key` directly. A vault with no `kdf_salt` but
+/// existing images, though, predates the single-master-key scheme (every file carried its own
+/// password-derived key, see the module doc comment), and there is no prior salt/nonce in the
+/// database to check a typed password against — silently accepting it as correct would make a
+/// typo'd or wrong password "succeed" and quietly lose access to every existing image. Fail with
+/// `AuthError::LegacyVaultUnverified` instead, and require the caller to go through
+/// `adopt_legacy_vault_password` as a separate, explicit step.
+pub async fn migrate_legacy_vault_key(
+    store: &dyn VaultStore,
+    password: &str,
+) -> std::result::Result<[u8; KEY_LEN], AuthError> {
+    if store
+        .get_vault_meta(META_SALT)
+        .await
+        .map_err(|_| AuthError::NotInitialized)?
+        .is_some()
+    {
+        return unlock_vault_key(store, password).await;
+    }
+
+    let has_images = !store
+        .get_all_images()
+        .await
+        .map_err(|_| AuthError::NotInitialized)?
+        .is_empty();
+
+    if has_images {
+        return Err(AuthError::LegacyVaultUnverified);
+    }
+
+    initialize_vault_key(store, password)
+        .await
+        .map_err(|_| AuthError::NotInitialized)
+}
+
+/// Explicitly adopt `password` as a legacy vault's going-forward master password, without any way
+/// to verify it against the vault's existing images (there is no prior salt/nonce to check it
+/// against — see [`AuthError::LegacyVaultUnverified`]). Every existing image is flagged
+/// `needs_reencryption` so the frontend can tell the user those files are unreadable under the
+/// newly adopted key until they're re-saved, instead of decryption just failing with no
+/// explanation. Only call this from a dedicated, explicit user action — never as a fallback
+/// inside normal unlock.
+pub async fn adopt_legacy_vault_password(store: &dyn VaultStore, password: &str) -> Result<[u8; KEY_LEN]> {
+    if store.get_vault_meta(META_SALT).await?.is_some() {
+        return Err(AuthError::AlreadyInitialized.into());
+    }
+
+    let key = initialize_vault_key(store, password).await?;
+    store.mark_all_images_needing_reencryption().await?;
+    Ok(key)
+}
@@ -0,0 +1,16 @@
+//! Shared vault core.
+//!
+//! Storage backends, the master-key scheme, content-defined chunking, capability tokens, and
+//! file encryption used to live directly in `src-tauri`, which meant the only way to touch a
+//! vault was through the GUI. Pulling them out into this crate lets `graphivault-cli` (and any
+//! future headless tooling, e.g. CI fixtures) operate on a vault without depending on Tauri.
+
+pub mod capability;
+pub mod chunking;
+pub mod database;
+pub mod encryption;
+pub mod error;
+pub mod keys;
+pub mod query;
+pub mod store;
+pub mod thumbnail;
@@ -0,0 +1,492 @@
+//! Structured search query language, compiled to a parameterized SQL `WHERE` clause so filtering
+//! runs inside SQLite instead of pulling every image back and filtering it in Rust or the Python
+//! backend.
+//!
+//! Grammar (keywords case-insensitive, parentheses for grouping):
+//! ```text
+//! expr      := or_expr
+//! or_expr   := and_expr ("OR" and_expr)*
+//! and_expr  := unary ("AND" unary)*
+//! unary     := "NOT" unary | atom
+//! atom      := "(" expr ")" | predicate
+//! predicate := "tag" ":" word
+//!            | "name" ":" glob          -- '*' matches any run of characters
+//!            | "added" ":" [cmp] date   -- e.g. added:>2024-01-01
+//!            | "size" ":" [cmp] size    -- e.g. size:>5mb
+//! cmp       := ">" | ">=" | "<" | "<=" | "="
+//! ```
+//!
+//! e.g. `tag:landscape AND (tag:2023 OR tag:2024) AND NOT tag:draft`
+
+use chrono::NaiveDate;
+
+/// The parsed query AST. `All` matches every (non-deleted) image, used for an empty query string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    All,
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Predicate(Predicate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparator {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Comparator {
+    fn sql_op(self) -> &'static str {
+        match self {
+            Comparator::Lt => "<",
+            Comparator::Le => "<=",
+            Comparator::Gt => ">",
+            Comparator::Ge => ">=",
+            Comparator::Eq => "=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Tag(String),
+    Name(String),                  // glob pattern
+    Added(Comparator, String),     // rfc3339 timestamp
+    Size(Comparator, i64),         // bytes
+}
+
+/// A value bound into the compiled `WHERE` clause, in the order its `?` placeholder appears.
+#[derive(Debug, Clone)]
+pub enum QueryParam {
+    Text(String),
+    Int(i64),
+}
+
+/// A query failed to parse; `position` is the byte offset of the offending token.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+fn tokenize(input: &str) -> Vec<(Token, usize)> {
+    let mut tokens = Vec::new();
+    let bytes = input.as_bytes();
+    let mut i = 0usize;
+    let mut word_start: Option<usize> = None;
+
+    let flush_word = |tokens: &mut Vec<(Token, usize)>, word_start: &mut Option<usize>, end: usize| {
+        if let Some(start) = word_start.take() {
+            let word = &input[start..end];
+            let token = match word.to_ascii_uppercase().as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                _ => Token::Term(word.to_string()),
+            };
+            tokens.push((token, start));
+        }
+    };
+
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_whitespace() {
+            flush_word(&mut tokens, &mut word_start, i);
+            i += 1;
+        } else if c == '(' {
+            flush_word(&mut tokens, &mut word_start, i);
+            tokens.push((Token::LParen, i));
+            i += 1;
+        } else if c == ')' {
+            flush_word(&mut tokens, &mut word_start, i);
+            tokens.push((Token::RParen, i));
+            i += 1;
+        } else {
+            if word_start.is_none() {
+                word_start = Some(i);
+            }
+            i += 1;
+        }
+    }
+    flush_word(&mut tokens, &mut word_start, bytes.len());
+
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_position(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(_, p)| *p)
+            .unwrap_or_else(|| self.tokens.last().map(|(_, p)| *p).unwrap_or(0))
+    }
+
+    fn advance(&mut self) -> Option<(Token, usize)> {
+        let item = self.tokens.get(self.pos).cloned();
+        if item.is_some() {
+            self.pos += 1;
+        }
+        item
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            message: message.into(),
+            position: self.peek_position(),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Query, ParseError> {
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Query, ParseError> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek(), Some(Token::Or)) {
+            self.advance();
+            let right = self.parse_and()?;
+            left = Query::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, ParseError> {
+        let mut left = self.parse_unary()?;
+        while matches!(self.peek(), Some(Token::And)) {
+            self.advance();
+            let right = self.parse_unary()?;
+            left = Query::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, ParseError> {
+        if matches!(self.peek(), Some(Token::Not)) {
+            self.advance();
+            let inner = self.parse_unary()?;
+            return Ok(Query::Not(Box::new(inner)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Query, ParseError> {
+        match self.peek() {
+            Some(Token::LParen) => {
+                self.advance();
+                let inner = self.parse_expr()?;
+                match self.advance() {
+                    Some((Token::RParen, _)) => Ok(inner),
+                    _ => Err(self.error("expected closing ')'")),
+                }
+            }
+            Some(Token::Term(_)) => {
+                let (token, position) = self.advance().unwrap();
+                let term = match token {
+                    Token::Term(s) => s,
+                    _ => unreachable!(),
+                };
+                Ok(Query::Predicate(parse_predicate(&term, position)?))
+            }
+            _ => Err(self.error("expected a predicate or '('")),
+        }
+    }
+}
+
+fn parse_comparator(value: &str) -> (Comparator, &str) {
+    if let Some(rest) = value.strip_prefix(">=") {
+        (Comparator::Ge, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Comparator::Le, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Comparator::Gt, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Comparator::Lt, rest)
+    } else if let Some(rest) = value.strip_prefix('=') {
+        (Comparator::Eq, rest)
+    } else {
+        (Comparator::Eq, value)
+    }
+}
+
+fn parse_size(value: &str, position: usize) -> Result<i64, ParseError> {
+    let lower = value.to_ascii_lowercase();
+    let (digits, multiplier) = if let Some(d) = lower.strip_suffix("gb") {
+        (d, 1024 * 1024 * 1024)
+    } else if let Some(d) = lower.strip_suffix("mb") {
+        (d, 1024 * 1024)
+    } else if let Some(d) = lower.strip_suffix("kb") {
+        (d, 1024)
+    } else if let Some(d) = lower.strip_suffix('b') {
+        (d, 1)
+    } else {
+        (lower.as_str(), 1)
+    };
+
+    let n: i64 = digits
+        .parse()
+        .map_err(|_| ParseError {
+            message: format!("invalid size '{}'", value),
+            position,
+        })?;
+
+    Ok(n * multiplier)
+}
+
+fn parse_predicate(term: &str, position: usize) -> Result<Predicate, ParseError> {
+    let (field, value) = term.split_once(':').ok_or_else(|| ParseError {
+        message: format!("expected 'field:value', found '{}'", term),
+        position,
+    })?;
+
+    if value.is_empty() {
+        return Err(ParseError {
+            message: format!("'{}' is missing a value", field),
+            position,
+        });
+    }
+
+    match field.to_ascii_lowercase().as_str() {
+        "tag" => Ok(Predicate::Tag(value.to_string())),
+        "name" => Ok(Predicate::Name(value.to_string())),
+        "added" => {
+            let (cmp, date_str) = parse_comparator(value);
+            let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").map_err(|_| ParseError {
+                message: format!("invalid date '{}', expected YYYY-MM-DD", date_str),
+                position,
+            })?;
+            let timestamp = date
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+                .to_rfc3339();
+            Ok(Predicate::Added(cmp, timestamp))
+        }
+        "size" => {
+            let (cmp, size_str) = parse_comparator(value);
+            Ok(Predicate::Size(cmp, parse_size(size_str, position)?))
+        }
+        other => Err(ParseError {
+            message: format!("unknown field '{}'", other),
+            position,
+        }),
+    }
+}
+
+/// Parse a query string into an AST. An empty (or all-whitespace) string parses as [`Query::All`].
+pub fn parse(input: &str) -> Result<Query, ParseError> {
+    if input.trim().is_empty() {
+        return Ok(Query::All);
+    }
+
+    let tokens = tokenize(input);
+    let mut parser = Parser { tokens, pos: 0 };
+    let query = parser.parse_expr()?;
+
+    if parser.pos != parser.tokens.len() {
+        return Err(parser.error("unexpected trailing input"));
+    }
+
+    Ok(query)
+}
+
+/// Translate a glob pattern (`*` = any run of characters, `?` = any single character) into a SQL
+/// `LIKE` pattern, escaping any literal `%`/`_`/`\` so they aren't mistaken for wildcards.
+fn glob_to_like(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '*' => out.push('%'),
+            '?' => out.push('_'),
+            '%' | '_' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Compile `query` into a SQL boolean expression (safe to embed inside a larger `WHERE` clause)
+/// plus the parameters its `?` placeholders bind to, in order.
+pub fn compile(query: &Query) -> (String, Vec<QueryParam>) {
+    match query {
+        Query::All => ("1 = 1".to_string(), Vec::new()),
+        Query::And(left, right) => combine(left, right, "AND"),
+        Query::Or(left, right) => combine(left, right, "OR"),
+        Query::Not(inner) => {
+            let (sql, params) = compile(inner);
+            (format!("NOT ({})", sql), params)
+        }
+        Query::Predicate(predicate) => compile_predicate(predicate),
+    }
+}
+
+fn combine(left: &Query, right: &Query, op: &str) -> (String, Vec<QueryParam>) {
+    let (left_sql, mut left_params) = compile(left);
+    let (right_sql, right_params) = compile(right);
+    left_params.extend(right_params);
+    (format!("({} {} {})", left_sql, op, right_sql), left_params)
+}
+
+fn compile_predicate(predicate: &Predicate) -> (String, Vec<QueryParam>) {
+    match predicate {
+        Predicate::Tag(name) => (
+            "EXISTS (SELECT 1 FROM tags WHERE tags.image_id = images.id AND tags.tag_name = ?)".to_string(),
+            vec![QueryParam::Text(name.clone())],
+        ),
+        Predicate::Name(pattern) => (
+            "images.file_name LIKE ? ESCAPE '\\'".to_string(),
+            vec![QueryParam::Text(glob_to_like(pattern))],
+        ),
+        Predicate::Added(cmp, timestamp) => (
+            format!("images.created_at {} ?", cmp.sql_op()),
+            vec![QueryParam::Text(timestamp.clone())],
+        ),
+        Predicate::Size(cmp, bytes) => (
+            format!("images.file_size {} ?", cmp.sql_op()),
+            vec![QueryParam::Int(*bytes)],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_is_all() {
+        assert_eq!(parse("").unwrap(), Query::All);
+        assert_eq!(parse("   ").unwrap(), Query::All);
+    }
+
+    #[test]
+    fn simple_predicates() {
+        assert_eq!(
+            parse("tag:landscape").unwrap(),
+            Query::Predicate(Predicate::Tag("landscape".to_string()))
+        );
+        assert_eq!(
+            parse("name:vacation*.jpg").unwrap(),
+            Query::Predicate(Predicate::Name("vacation*.jpg".to_string()))
+        );
+        assert_eq!(
+            parse("size:>5mb").unwrap(),
+            Query::Predicate(Predicate::Size(Comparator::Gt, 5 * 1024 * 1024))
+        );
+        assert_eq!(
+            parse("added:>=2024-01-01").unwrap(),
+            Query::Predicate(Predicate::Added(Comparator::Ge, "2024-01-01T00:00:00+00:00".to_string()))
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_which_binds_tighter_than_or() {
+        let query = parse("tag:a OR tag:b AND NOT tag:c").unwrap();
+        let expected = Query::Or(
+            Box::new(Query::Predicate(Predicate::Tag("a".to_string()))),
+            Box::new(Query::And(
+                Box::new(Query::Predicate(Predicate::Tag("b".to_string()))),
+                Box::new(Query::Not(Box::new(Query::Predicate(Predicate::Tag(
+                    "c".to_string(),
+                ))))),
+            )),
+        );
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        let query = parse("(tag:a OR tag:b) AND tag:c").unwrap();
+        let expected = Query::And(
+            Box::new(Query::Or(
+                Box::new(Query::Predicate(Predicate::Tag("a".to_string()))),
+                Box::new(Query::Predicate(Predicate::Tag("b".to_string()))),
+            )),
+            Box::new(Query::Predicate(Predicate::Tag("c".to_string()))),
+        );
+        assert_eq!(query, expected);
+    }
+
+    #[test]
+    fn keywords_are_case_insensitive() {
+        assert_eq!(parse("tag:a and tag:b").unwrap(), parse("tag:a AND tag:b").unwrap());
+    }
+
+    #[test]
+    fn unknown_field_is_a_parse_error() {
+        let err = parse("color:red").unwrap_err();
+        assert_eq!(err.message, "unknown field 'color'");
+    }
+
+    #[test]
+    fn missing_value_is_a_parse_error() {
+        let err = parse("tag:").unwrap_err();
+        assert!(err.message.contains("missing a value"));
+    }
+
+    #[test]
+    fn unbalanced_parenthesis_is_a_parse_error() {
+        assert!(parse("(tag:a").is_err());
+        assert!(parse("tag:a)").is_err());
+    }
+
+    #[test]
+    fn invalid_date_is_a_parse_error() {
+        assert!(parse("added:>not-a-date").is_err());
+    }
+
+    #[test]
+    fn glob_to_like_escapes_sql_wildcards() {
+        assert_eq!(glob_to_like("vacation*.jpg"), "vacation%.jpg");
+        assert_eq!(glob_to_like("a?b"), "a_b");
+        assert_eq!(glob_to_like("100%_done"), "100\\%\\_done");
+    }
+
+    #[test]
+    fn compile_tag_predicate_binds_a_single_text_param() {
+        let (sql, params) = compile(&Query::Predicate(Predicate::Tag("trip".to_string())));
+        assert!(sql.contains("tags.tag_name = ?"));
+        assert_eq!(params.len(), 1);
+        assert!(matches!(&params[0], QueryParam::Text(v) if v == "trip"));
+    }
+
+    #[test]
+    fn compile_not_wraps_inner_sql() {
+        let (sql, _) = compile(&Query::Not(Box::new(Query::Predicate(Predicate::Tag(
+            "draft".to_string(),
+        )))));
+        assert!(sql.starts_with("NOT ("));
+    }
+}
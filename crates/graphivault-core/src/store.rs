@@ -0,0 +1,264 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::database::{
+    self, AnnotationRecord, ImageQuery, ImageRecord, ImageVariantRecord, IndexResult,
+    IssuedTokenRecord, TagHistoryRecord, TagRecord, VariantPreset,
+};
+use crate::query::{self, Query};
+
+/// Storage backend abstraction for the vault's metadata and audit log.
+///
+/// Every command used to pull a `SqlitePool` straight out of Tauri's managed state, which tied
+/// the whole vault to a live `AppHandle` and made it impossible to run against a shared/remote
+/// database or to unit-test without standing up a full Tauri app. Implementors of this trait own
+/// a connection (or pool of connections) to whatever backend they wrap and are plugged into
+/// `tauri::Builder::manage` as a trait object instead of a concrete pool type. Which backend gets
+/// constructed is driven by the `storage_backend` key in `vault_meta` (see `select_store`),
+/// currently `sqlite` only; `postgres`/`mysql` implementations can be added later without
+/// touching a single command.
+#[async_trait]
+pub trait VaultStore: Send + Sync {
+    /// Apply this backend's schema. Safe to call on every startup.
+    async fn migrate(&self) -> Result<()>;
+
+    async fn insert_image(&self, image: &ImageRecord) -> Result<i64>;
+    async fn get_all_images(&self) -> Result<Vec<ImageRecord>>;
+    /// Flag every non-deleted image as `needs_reencryption`. Only called when a legacy vault's
+    /// password is adopted without verification (see `keys::adopt_legacy_vault_password`).
+    async fn mark_all_images_needing_reencryption(&self) -> Result<()>;
+    async fn list_images(&self, query: &ImageQuery) -> Result<IndexResult>;
+    async fn get_image_by_id(&self, id: i64) -> Result<Option<ImageRecord>>;
+    async fn get_image_by_hash(&self, file_hash: &str) -> Result<Option<ImageRecord>>;
+    async fn soft_delete_image(&self, id: i64) -> Result<()>;
+    /// Run a parsed structured search query (see [`crate::query`]) against non-deleted images.
+    async fn search_images(&self, query: &Query) -> Result<Vec<ImageRecord>>;
+
+    async fn insert_tag(&self, tag: &TagRecord) -> Result<i64>;
+    async fn get_image_tags(&self, image_id: i64) -> Result<Vec<TagRecord>>;
+    async fn get_tag_history(&self, image_id: i64) -> Result<Vec<TagHistoryRecord>>;
+    async fn restore_tag(&self, history_id: i64) -> Result<i64>;
+
+    async fn insert_annotation(&self, annotation: &AnnotationRecord) -> Result<i64>;
+    async fn get_image_annotations(&self, image_id: i64) -> Result<Vec<AnnotationRecord>>;
+
+    async fn insert_variant(&self, variant: &ImageVariantRecord) -> Result<i64>;
+    async fn get_variants(&self, image_id: i64) -> Result<Vec<ImageVariantRecord>>;
+    async fn get_variant(&self, image_id: i64, preset_name: &str) -> Result<Option<ImageVariantRecord>>;
+    async fn get_variant_presets(&self) -> Result<Vec<VariantPreset>>;
+
+    async fn set_vault_meta(&self, key: &str, value: &str) -> Result<()>;
+    async fn get_vault_meta(&self, key: &str) -> Result<Option<String>>;
+
+    /// Record `hash` if new (returning `true`), or bump its refcount if another image already
+    /// contains an identical chunk (returning `false`).
+    async fn touch_chunk(&self, hash: &str, size: i64) -> Result<bool>;
+    async fn link_image_chunk(&self, image_id: i64, seq: i64, chunk_hash: &str) -> Result<()>;
+    async fn get_image_chunk_hashes(&self, image_id: i64) -> Result<Vec<String>>;
+    async fn unlink_image_chunks(&self, image_id: i64) -> Result<()>;
+    /// Drop one reference to `hash`, returning its refcount afterward.
+    async fn decrement_chunk_refcount(&self, hash: &str) -> Result<i64>;
+    async fn remove_chunk_record(&self, hash: &str) -> Result<()>;
+
+    async fn log_auth_event(&self, event_type: &str, status: &str, details: Option<&str>) -> Result<()>;
+
+    async fn insert_issued_token(
+        &self,
+        token_id: &str,
+        resource_kind: &str,
+        resource_value: &str,
+        ops: &str,
+        expires_at: &str,
+    ) -> Result<()>;
+    async fn list_issued_tokens(&self) -> Result<Vec<IssuedTokenRecord>>;
+    async fn get_issued_token(&self, token_id: &str) -> Result<Option<IssuedTokenRecord>>;
+    async fn revoke_issued_token(&self, token_id: &str) -> Result<()>;
+}
+
+/// Default, built-in backend: a single local SQLite file, wrapping today's `sqlx` logic.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Connect to `database_url` and wrap the resulting pool. Does not run migrations; call
+    /// [`VaultStore::migrate`] once the store is constructed.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        Ok(Self::new(database::connect(database_url).await?))
+    }
+}
+
+#[async_trait]
+impl VaultStore for SqliteStore {
+    async fn migrate(&self) -> Result<()> {
+        database::migrate_sqlite(&self.pool).await
+    }
+
+    async fn insert_image(&self, image: &ImageRecord) -> Result<i64> {
+        database::insert_image(&self.pool, image).await
+    }
+
+    async fn get_all_images(&self) -> Result<Vec<ImageRecord>> {
+        database::get_all_images(&self.pool).await
+    }
+
+    async fn mark_all_images_needing_reencryption(&self) -> Result<()> {
+        database::mark_all_images_needing_reencryption(&self.pool).await
+    }
+
+    async fn list_images(&self, query: &ImageQuery) -> Result<IndexResult> {
+        database::list_images(&self.pool, query).await
+    }
+
+    async fn get_image_by_id(&self, id: i64) -> Result<Option<ImageRecord>> {
+        database::get_image_by_id(&self.pool, id).await
+    }
+
+    async fn get_image_by_hash(&self, file_hash: &str) -> Result<Option<ImageRecord>> {
+        database::get_image_by_hash(&self.pool, file_hash).await
+    }
+
+    async fn soft_delete_image(&self, id: i64) -> Result<()> {
+        database::soft_delete_image(&self.pool, id).await
+    }
+
+    async fn search_images(&self, query: &Query) -> Result<Vec<ImageRecord>> {
+        let (where_sql, params) = query::compile(query);
+        database::search_images(&self.pool, &where_sql, &params).await
+    }
+
+    async fn insert_tag(&self, tag: &TagRecord) -> Result<i64> {
+        database::insert_tag(&self.pool, tag).await
+    }
+
+    async fn get_image_tags(&self, image_id: i64) -> Result<Vec<TagRecord>> {
+        database::get_image_tags(&self.pool, image_id).await
+    }
+
+    async fn get_tag_history(&self, image_id: i64) -> Result<Vec<TagHistoryRecord>> {
+        database::get_tag_history(&self.pool, image_id).await
+    }
+
+    async fn restore_tag(&self, history_id: i64) -> Result<i64> {
+        database::restore_tag(&self.pool, history_id).await
+    }
+
+    async fn insert_annotation(&self, annotation: &AnnotationRecord) -> Result<i64> {
+        database::insert_annotation(&self.pool, annotation).await
+    }
+
+    async fn get_image_annotations(&self, image_id: i64) -> Result<Vec<AnnotationRecord>> {
+        database::get_image_annotations(&self.pool, image_id).await
+    }
+
+    async fn insert_variant(&self, variant: &ImageVariantRecord) -> Result<i64> {
+        database::insert_variant(&self.pool, variant).await
+    }
+
+    async fn get_variants(&self, image_id: i64) -> Result<Vec<ImageVariantRecord>> {
+        database::get_variants(&self.pool, image_id).await
+    }
+
+    async fn get_variant(&self, image_id: i64, preset_name: &str) -> Result<Option<ImageVariantRecord>> {
+        database::get_variant(&self.pool, image_id, preset_name).await
+    }
+
+    async fn get_variant_presets(&self) -> Result<Vec<VariantPreset>> {
+        database::get_variant_presets(&self.pool).await
+    }
+
+    async fn set_vault_meta(&self, key: &str, value: &str) -> Result<()> {
+        database::set_vault_meta(&self.pool, key, value).await
+    }
+
+    async fn get_vault_meta(&self, key: &str) -> Result<Option<String>> {
+        database::get_vault_meta(&self.pool, key).await
+    }
+
+    async fn touch_chunk(&self, hash: &str, size: i64) -> Result<bool> {
+        database::touch_chunk(&self.pool, hash, size).await
+    }
+
+    async fn link_image_chunk(&self, image_id: i64, seq: i64, chunk_hash: &str) -> Result<()> {
+        database::link_image_chunk(&self.pool, image_id, seq, chunk_hash).await
+    }
+
+    async fn get_image_chunk_hashes(&self, image_id: i64) -> Result<Vec<String>> {
+        database::get_image_chunk_hashes(&self.pool, image_id).await
+    }
+
+    async fn unlink_image_chunks(&self, image_id: i64) -> Result<()> {
+        database::unlink_image_chunks(&self.pool, image_id).await
+    }
+
+    async fn decrement_chunk_refcount(&self, hash: &str) -> Result<i64> {
+        database::decrement_chunk_refcount(&self.pool, hash).await
+    }
+
+    async fn remove_chunk_record(&self, hash: &str) -> Result<()> {
+        database::remove_chunk_record(&self.pool, hash).await
+    }
+
+    async fn log_auth_event(&self, event_type: &str, status: &str, details: Option<&str>) -> Result<()> {
+        database::log_auth_event(&self.pool, event_type, status, details).await
+    }
+
+    async fn insert_issued_token(
+        &self,
+        token_id: &str,
+        resource_kind: &str,
+        resource_value: &str,
+        ops: &str,
+        expires_at: &str,
+    ) -> Result<()> {
+        database::insert_issued_token(&self.pool, token_id, resource_kind, resource_value, ops, expires_at).await
+    }
+
+    async fn list_issued_tokens(&self) -> Result<Vec<IssuedTokenRecord>> {
+        database::list_issued_tokens(&self.pool).await
+    }
+
+    async fn get_issued_token(&self, token_id: &str) -> Result<Option<IssuedTokenRecord>> {
+        database::get_issued_token(&self.pool, token_id).await
+    }
+
+    async fn revoke_issued_token(&self, token_id: &str) -> Result<()> {
+        database::revoke_issued_token(&self.pool, token_id).await
+    }
+}
+
+/// Build the configured backend. `backend`, when given (e.g. a CLI flag), overrides and persists
+/// the `storage_backend` key in `vault_meta`; otherwise the previously stored choice is used, and
+/// a brand-new vault with no stored choice yet defaults to `sqlite`. `vault_meta` may not exist on
+/// a fresh vault, so this bootstraps a sqlite connection and migrates it before reading or writing
+/// that key (`migrate` is safe to call more than once, so the caller's own post-`select_store`
+/// migrate call is still harmless). Only `sqlite` is actually implemented today — anything else
+/// fails loudly rather than silently presenting an unimplemented backend as selected.
+pub async fn select_store(database_url: &str, backend: Option<&str>) -> Result<Box<dyn VaultStore>> {
+    let store = SqliteStore::connect(database_url).await?;
+    store.migrate().await?;
+
+    let chosen = match backend {
+        Some(requested) => {
+            store.set_vault_meta("storage_backend", requested).await?;
+            requested.to_string()
+        }
+        None => store
+            .get_vault_meta("storage_backend")
+            .await?
+            .unwrap_or_else(|| "sqlite".to_string()),
+    };
+
+    match chosen.as_str() {
+        "sqlite" => Ok(Box::new(store)),
+        other => Err(anyhow::anyhow!(
+            "unsupported storage_backend '{}': only sqlite is implemented",
+            other
+        )),
+    }
+}
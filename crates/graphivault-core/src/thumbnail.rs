@@ -0,0 +1,160 @@
+//! On-disk, encrypted thumbnail cache, so grid rendering doesn't have to decrypt and decode the
+//! full original on every repaint.
+//!
+//! A thumbnail is rendered according to the `thumb` entry in [`crate::database::get_variant_presets`]
+//! (falling back to 256px JPEG if that preset is missing) and cached at `vault/thumbnails/<image_id>`
+//! the same way a chunk is cached (random-nonce-prefixed XChaCha20-Poly1305, see [`crate::chunking`]),
+//! except the cached file also carries the source `file_hash` it was rendered from, stored ahead of
+//! the nonce in the clear. That lets a cache lookup tell whether the source image has since
+//! changed and the thumbnail needs regenerating, without decrypting anything first. Every render
+//! is also registered in `image_variants` (see [`crate::store::VaultStore::insert_variant`]) so
+//! it's visible through the same rendition-tracking path `register_image_variant` uses, instead of
+//! being a second, untracked thumbnail system.
+
+use anyhow::{anyhow, Result};
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use image::imageops::FilterType;
+use rand::RngCore;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::database::{ImageVariantRecord, VariantPreset};
+use crate::keys::KEY_LEN;
+use crate::store::VaultStore;
+
+const NONCE_LEN: usize = 24;
+const THUMB_PRESET_NAME: &str = "thumb";
+
+fn fallback_preset() -> VariantPreset {
+    VariantPreset {
+        name: THUMB_PRESET_NAME.to_string(),
+        format: "jpeg".to_string(),
+        max_dimension: 256,
+    }
+}
+
+fn image_format_for(preset_format: &str) -> image::ImageFormat {
+    match preset_format.to_ascii_lowercase().as_str() {
+        "webp" => image::ImageFormat::WebP,
+        "png" => image::ImageFormat::Png,
+        _ => image::ImageFormat::Jpeg,
+    }
+}
+
+fn thumbnail_path(vault_dir: &Path, image_id: i64) -> PathBuf {
+    vault_dir.join("thumbnails").join(image_id.to_string())
+}
+
+/// Decode `plaintext`, fit it within `max_dimension` x `max_dimension` preserving aspect ratio,
+/// and re-encode in `format`. Returns the encoded bytes and the rendered width/height.
+fn render(plaintext: &[u8], max_dimension: u32, format: image::ImageFormat) -> Result<(Vec<u8>, u32, u32)> {
+    let decoded = image::load_from_memory(plaintext)?;
+    let thumbnail = decoded.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    let (width, height) = (thumbnail.width(), thumbnail.height());
+
+    let mut encoded = Vec::new();
+    thumbnail.write_to(&mut Cursor::new(&mut encoded), format)?;
+    Ok((encoded, width, height))
+}
+
+fn encrypt_and_write(
+    vault_dir: &Path,
+    key: &[u8; KEY_LEN],
+    image_id: i64,
+    file_hash: &str,
+    encoded: &[u8],
+) -> Result<()> {
+    let path = thumbnail_path(vault_dir, image_id);
+    std::fs::create_dir_all(path.parent().ok_or_else(|| anyhow!("invalid thumbnail path"))?)?;
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), encoded)
+        .map_err(|e| anyhow!("failed to encrypt thumbnail for image {}: {}", image_id, e))?;
+
+    let hash_bytes = file_hash.as_bytes();
+    let mut contents = Vec::with_capacity(1 + hash_bytes.len() + NONCE_LEN + ciphertext.len());
+    contents.push(hash_bytes.len() as u8);
+    contents.extend_from_slice(hash_bytes);
+    contents.extend_from_slice(&nonce_bytes);
+    contents.extend_from_slice(&ciphertext);
+    std::fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Return the cached thumbnail for `image_id` if one exists and was rendered from `file_hash`;
+/// `None` means the caller should render a fresh one (missing, or the source has since changed).
+pub fn read_cached(vault_dir: &Path, key: &[u8; KEY_LEN], image_id: i64, file_hash: &str) -> Result<Option<Vec<u8>>> {
+    let path = thumbnail_path(vault_dir, image_id);
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let Some(&hash_len) = contents.first() else {
+        return Ok(None);
+    };
+    let hash_len = hash_len as usize;
+    if contents.len() < 1 + hash_len + NONCE_LEN {
+        return Ok(None);
+    }
+
+    let cached_hash = std::str::from_utf8(&contents[1..1 + hash_len])?;
+    if cached_hash != file_hash {
+        return Ok(None);
+    }
+
+    let (nonce_bytes, ciphertext) = contents[1 + hash_len..].split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| anyhow!("invalid key: {}", e))?;
+    let plaintext = cipher
+        .decrypt(XNonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|e| anyhow!("failed to decrypt cached thumbnail for image {}: {}", image_id, e))?;
+
+    Ok(Some(plaintext))
+}
+
+/// Render a thumbnail from `plaintext` (the fully decrypted original) using the `thumb` entry in
+/// `store.get_variant_presets()` (falling back to 256px JPEG if that preset isn't configured),
+/// cache it for `image_id` tagged with `file_hash`, register it in `image_variants`, and return
+/// the encoded (not yet cache-encrypted) bytes.
+pub async fn render_and_cache(
+    store: &dyn VaultStore,
+    vault_dir: &Path,
+    key: &[u8; KEY_LEN],
+    image_id: i64,
+    file_hash: &str,
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    let preset = store
+        .get_variant_presets()
+        .await?
+        .into_iter()
+        .find(|p| p.name == THUMB_PRESET_NAME)
+        .unwrap_or_else(fallback_preset);
+
+    let format = image_format_for(&preset.format);
+    let (encoded, width, height) = render(plaintext, preset.max_dimension, format)?;
+    encrypt_and_write(vault_dir, key, image_id, file_hash, &encoded)?;
+
+    store
+        .insert_variant(&ImageVariantRecord {
+            id: 0,
+            image_id,
+            preset_name: preset.name,
+            format: preset.format,
+            width: width as i64,
+            height: height as i64,
+            storage_path: thumbnail_path(vault_dir, image_id).to_string_lossy().to_string(),
+            byte_size: encoded.len() as i64,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })
+        .await?;
+
+    Ok(encoded)
+}
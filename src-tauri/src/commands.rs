@@ -1,11 +1,23 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tauri::State;
 
 // Re-export database models
-pub use crate::database::{AnnotationRecord, ImageRecord, TagRecord};
+pub use crate::database::{
+    AnnotationRecord, ImageQuery, ImageRecord, ImageVariantRecord, IndexResult, IssuedTokenRecord,
+    TagHistoryRecord, TagRecord, VariantPreset,
+};
+use crate::capability::CapabilityResource;
+use crate::keys::VaultKeyState;
+use crate::python_worker::PythonBackend;
+use crate::store::VaultStore;
+pub use graphivault_core::error::VaultError;
+
+type Store<'r> = State<'r, Arc<dyn VaultStore>>;
+type KeyState<'r> = State<'r, VaultKeyState>;
+type Python<'r> = State<'r, Arc<PythonBackend>>;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PythonBackendResponse {
@@ -14,130 +26,50 @@ pub struct PythonBackendResponse {
     pub error: Option<String>,
 }
 
-// Python backend integration helper
+/// Call `method` on the long-lived Python backend worker (see [`crate::python_worker`]) instead
+/// of spawning a fresh process for every command.
 async fn call_python_backend(
-    app_handle: &tauri::AppHandle,
+    python: &Python<'_>,
     method: &str,
     args: &HashMap<String, serde_json::Value>,
-) -> Result<PythonBackendResponse, String> {
-    let _app_data_dir = app_handle
-        .path_resolver()
-        .app_data_dir()
-        .ok_or("Failed to get app data directory")?;
-
-    // Use test vault for now
-    let vault_path = std::path::PathBuf::from("D:\\GraphiVault\\test_vault");
-
-    // In development, look for python_backend in the project root
-    // In production, look for it in the resource directory
-    let python_backend_path = if cfg!(debug_assertions) {
-        // Development mode - find project root by looking for package.json
-        let mut current_dir = std::env::current_dir().unwrap();
-
-        // Look for project root (contains package.json and python_backend)
-        loop {
-            let package_json = current_dir.join("package.json");
-            let python_backend = current_dir.join("python_backend");
-
-            if package_json.exists() && python_backend.exists() {
-                break current_dir.join("python_backend");
-            }
-
-            match current_dir.parent() {
-                Some(parent) => current_dir = parent.to_path_buf(),
-                None => {
-                    // Fallback to absolute path if we can't find project root
-                    break std::path::PathBuf::from("D:\\GraphiVault\\python_backend");
-                }
-            }
-        }
-    } else {
-        // Production mode - look in resource directory
-        app_handle
-            .path_resolver()
-            .resource_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap())
-            .join("python_backend")
-    };
-
-    // Prepare arguments
-    let mut cmd_args = vec![
-        python_backend_path
-            .join("main.py")
-            .to_string_lossy()
-            .to_string(),
-        method.to_string(), // Command as positional argument
-        "--vault-path".to_string(),
-        vault_path.to_string_lossy().to_string(),
-    ];
-
-    for (key, value) in args {
-        cmd_args.push(format!("--{}", key));
-        cmd_args.push(value.to_string().trim_matches('"').to_string());
-    }
-
-    // Debug logging for path resolution
-    println!("🐍 Python backend path: {:?}", python_backend_path);
-    println!("🐍 Main.py path: {:?}", python_backend_path.join("main.py"));
-    println!("🐍 Command args: {:?}", cmd_args);
-
-    let output = std::process::Command::new("python")
-        .args(&cmd_args)
-        .output()
-        .map_err(|e| format!("Failed to execute Python backend: {}", e))?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
-    // Enhanced debug logging
-    println!("🐍 Python exit code: {:?}", output.status.code());
-    println!("🐍 Python stdout: {}", stdout);
-    println!("🐍 Python stderr: {}", stderr);
-
-    if !output.status.success() {
-        return Err(format!("Python backend error: {}", stderr));
-    }
-
-    if stdout.trim().is_empty() {
-        return Err(format!(
-            "Python backend returned empty response. stderr: {}",
-            stderr
-        ));
-    }
-
-    serde_json::from_str(&stdout).map_err(|e| {
-        format!(
-            "Failed to parse Python backend response: {}. Raw output: {}",
-            e, stdout
-        )
+) -> Result<PythonBackendResponse, VaultError> {
+    let result = python
+        .call(method, args)
+        .await
+        .map_err(|e| VaultError::Backend {
+            method: method.to_string(),
+            stderr: e.to_string(),
+        })?;
+
+    serde_json::from_value(result).map_err(|e| VaultError::Backend {
+        method: method.to_string(),
+        stderr: format!("failed to parse response: {}", e),
     })
 }
 
 #[tauri::command]
-pub async fn get_app_data_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn get_app_data_dir(app_handle: tauri::AppHandle) -> Result<String, VaultError> {
     let app_data_dir = app_handle
         .path_resolver()
         .app_data_dir()
-        .ok_or("Failed to get app data directory")?;
+        .ok_or_else(|| VaultError::Other(anyhow::anyhow!("Failed to get app data directory")))?;
 
     Ok(app_data_dir.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-pub async fn init_database(db: State<'_, SqlitePool>) -> Result<(), String> {
-    crate::database::init_db(&db)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn init_database(db: Store<'_>) -> Result<(), VaultError> {
+    db.migrate().await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn add_image(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     file_hash: String,
     file_name: String,
     storage_path: String,
     file_size: i64,
-) -> Result<i64, String> {
+) -> Result<i64, VaultError> {
     let now = Utc::now().to_rfc3339();
 
     let image_record = ImageRecord {
@@ -149,75 +81,79 @@ pub async fn add_image(
         updated_at: now,
         file_size,
         is_deleted: false,
+        needs_reencryption: false,
     };
-    let image_id = crate::database::insert_image(&db, &image_record)
-        .await
-        .map_err(|e| e.to_string())?;
+    let image_id = db.insert_image(&image_record).await.map_err(VaultError::from)?;
 
     // Log the event
-    crate::database::log_auth_event(
-        &db,
+    db.log_auth_event(
         "image_added",
         "success",
         Some(&format!("Image ID: {}", image_id)),
     )
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(VaultError::from)?;
 
     Ok(image_id)
 }
 
 #[tauri::command]
-pub async fn get_images(db: State<'_, SqlitePool>) -> Result<Vec<ImageRecord>, String> {
-    crate::database::get_all_images(&db)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn get_images(db: Store<'_>) -> Result<Vec<ImageRecord>, VaultError> {
+    db.get_all_images().await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn list_images(db: Store<'_>, query: ImageQuery) -> Result<IndexResult, VaultError> {
+    db.list_images(&query).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_image_by_id(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     id: i64,
-) -> Result<Option<ImageRecord>, String> {
-    crate::database::get_image_by_id(&db, id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Option<ImageRecord>, VaultError> {
+    db.get_image_by_id(id).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_image_by_hash(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     file_hash: String,
-) -> Result<Option<ImageRecord>, String> {
-    crate::database::get_image_by_hash(&db, &file_hash)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Option<ImageRecord>, VaultError> {
+    db.get_image_by_hash(&file_hash).await.map_err(VaultError::from)
 }
 
+/// Soft-delete `id` and garbage-collect its chunks: unlink it from `image_chunks`, decrement the
+/// refcount of every chunk it referenced, and remove any chunk (row and file) left with no other
+/// references. Without this, every image deleted through the normal path would leak its chunk
+/// rows and files forever.
 #[tauri::command]
-pub async fn delete_image(db: State<'_, SqlitePool>, id: i64) -> Result<(), String> {
-    crate::database::soft_delete_image(&db, id)
+pub async fn delete_image(app_handle: tauri::AppHandle, db: Store<'_>, id: i64) -> Result<(), VaultError> {
+    db.soft_delete_image(id).await.map_err(VaultError::from)?;
+
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+    crate::chunking::delete_image_chunks(db.inner().as_ref(), &vault_dir, id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(VaultError::from)?;
+
     // Log the event
-    crate::database::log_auth_event(
-        &db,
+    db.log_auth_event(
         "image_deleted",
         "success",
         Some(&format!("Image ID: {}", id)),
     )
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(VaultError::from)?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn add_tag(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     image_id: i64,
     tag_name: String,
     tag_type: Option<String>,
-) -> Result<i64, String> {
+) -> Result<i64, VaultError> {
     let tag_record = TagRecord {
         id: 0, // Will be auto-generated
         image_id,
@@ -225,84 +161,124 @@ pub async fn add_tag(
         tag_type,
         created_at: Utc::now().to_rfc3339(),
     };
-    crate::database::insert_tag(&db, &tag_record)
-        .await
-        .map_err(|e| e.to_string())
+    db.insert_tag(&tag_record).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_image_tags(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     image_id: i64,
-) -> Result<Vec<TagRecord>, String> {
-    crate::database::get_image_tags(&db, image_id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<TagRecord>, VaultError> {
+    db.get_image_tags(image_id).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn get_tag_history(
+    db: Store<'_>,
+    image_id: i64,
+) -> Result<Vec<TagHistoryRecord>, VaultError> {
+    db.get_tag_history(image_id).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn restore_tag(db: Store<'_>, history_id: i64) -> Result<i64, VaultError> {
+    db.restore_tag(history_id).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn add_annotation(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     image_id: i64,
     note: String,
-) -> Result<i64, String> {
+) -> Result<i64, VaultError> {
     let annotation_record = AnnotationRecord {
         id: 0, // Will be auto-generated
         image_id,
         note,
         created_at: Utc::now().to_rfc3339(),
     };
-    crate::database::insert_annotation(&db, &annotation_record)
-        .await
-        .map_err(|e| e.to_string())
+    db.insert_annotation(&annotation_record).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_image_annotations(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     image_id: i64,
-) -> Result<Vec<AnnotationRecord>, String> {
-    crate::database::get_image_annotations(&db, image_id)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<AnnotationRecord>, VaultError> {
+    db.get_image_annotations(image_id).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn register_image_variant(
+    db: Store<'_>,
+    image_id: i64,
+    preset_name: String,
+    format: String,
+    width: i64,
+    height: i64,
+    storage_path: String,
+    byte_size: i64,
+) -> Result<i64, VaultError> {
+    let variant = ImageVariantRecord {
+        id: 0, // Will be auto-generated
+        image_id,
+        preset_name,
+        format,
+        width,
+        height,
+        storage_path,
+        byte_size,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    db.insert_variant(&variant).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn get_image_variants(
+    db: Store<'_>,
+    image_id: i64,
+) -> Result<Vec<ImageVariantRecord>, VaultError> {
+    db.get_variants(image_id).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn get_image_variant(
+    db: Store<'_>,
+    image_id: i64,
+    preset_name: String,
+) -> Result<Option<ImageVariantRecord>, VaultError> {
+    db.get_variant(image_id, &preset_name).await.map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn get_variant_presets(db: Store<'_>) -> Result<Vec<VariantPreset>, VaultError> {
+    db.get_variant_presets().await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn set_vault_setting(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     key: String,
     value: String,
-) -> Result<(), String> {
-    crate::database::set_vault_meta(&db, &key, &value)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<(), VaultError> {
+    db.set_vault_meta(&key, &value).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_vault_setting(
-    db: State<'_, SqlitePool>,
+    db: Store<'_>,
     key: String,
-) -> Result<Option<String>, String> {
-    crate::database::get_vault_meta(&db, &key)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Option<String>, VaultError> {
+    db.get_vault_meta(&key).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
-pub async fn get_vault_info(db: State<'_, SqlitePool>) -> Result<serde_json::Value, String> {
-    let vault_id = crate::database::get_vault_meta(&db, "vault_id")
-        .await
-        .map_err(|e| e.to_string())?;
-    let created_at = crate::database::get_vault_meta(&db, "created_at")
-        .await
-        .map_err(|e| e.to_string())?;
-    let schema_version = crate::database::get_vault_meta(&db, "schema_version")
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn get_vault_info(db: Store<'_>) -> Result<serde_json::Value, VaultError> {
+    let vault_id = db.get_vault_meta("vault_id").await.map_err(VaultError::from)?;
+    let created_at = db.get_vault_meta("created_at").await.map_err(VaultError::from)?;
+    let schema_version = db.get_vault_meta("schema_version").await.map_err(VaultError::from)?;
     // Get image count
-    let images = crate::database::get_all_images(&db)
-        .await
-        .map_err(|e| e.to_string())?;
+    let images = db.get_all_images().await.map_err(VaultError::from)?;
     let vault_info = serde_json::json!({
         "vault_id": vault_id,
         "created_at": created_at,
@@ -313,65 +289,192 @@ pub async fn get_vault_info(db: State<'_, SqlitePool>) -> Result<serde_json::Val
     Ok(vault_info)
 }
 
+/// Grant access to either an explicit set of image ids or every image carrying any of a set of
+/// tags; exactly one of `image_ids`/`tags` should be non-empty.
+#[tauri::command]
+pub async fn grant_capability(
+    db: Store<'_>,
+    image_ids: Vec<i64>,
+    tags: Vec<String>,
+    ttl_seconds: i64,
+    ops: Vec<String>,
+) -> Result<String, VaultError> {
+    let resource = if !tags.is_empty() {
+        CapabilityResource::TagNames(tags)
+    } else {
+        CapabilityResource::ImageIds(image_ids)
+    };
+    let ops: Vec<&str> = ops.iter().map(String::as_str).collect();
+
+    crate::capability::grant_capability(db.inner().as_ref(), resource, Duration::seconds(ttl_seconds), &ops)
+        .await
+        .map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn list_capabilities(db: Store<'_>) -> Result<Vec<IssuedTokenRecord>, VaultError> {
+    crate::capability::list_capabilities(db.inner().as_ref())
+        .await
+        .map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn revoke_capability(db: Store<'_>, token_id: String) -> Result<(), VaultError> {
+    crate::capability::revoke_capability(db.inner().as_ref(), &token_id)
+        .await
+        .map_err(VaultError::from)
+}
+
+/// Validate `token` for `view` access to `image_id`, then reassemble and decrypt it from the
+/// chunk store. The capability check happens before anything is read off disk, so a redeemed
+/// token can only ever reach images within its own claimed set.
+#[tauri::command]
+pub async fn redeem_capability(
+    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
+    token: String,
+    image_id: i64,
+) -> Result<Vec<u8>, VaultError> {
+    crate::capability::redeem_capability(db.inner().as_ref(), &token, image_id, "view")
+        .await
+        .map_err(VaultError::from)?;
+
+    let key = current_master_key(&key_state).await?;
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+
+    crate::chunking::reassemble_image(db.inner().as_ref(), &vault_dir, &key, image_id)
+        .await
+        .map_err(VaultError::from)
+}
+
+async fn current_master_key(key_state: &KeyState<'_>) -> Result<[u8; crate::keys::KEY_LEN], VaultError> {
+    let guard = key_state.0.read().await;
+    (*guard).ok_or(VaultError::VaultLocked)
+}
+
 #[tauri::command]
-pub async fn encrypt_file(file_path: String, password: String) -> Result<String, String> {
-    crate::encryption::encrypt_file(&file_path, &password)
+pub async fn encrypt_file(key_state: KeyState<'_>, file_path: String) -> Result<String, VaultError> {
+    let key = current_master_key(&key_state).await?;
+    crate::encryption::encrypt_file(&file_path, &key)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| VaultError::CorruptData {
+            path: file_path,
+            detail: e.to_string(),
+        })
 }
 
 #[tauri::command]
 pub async fn decrypt_file(
+    key_state: KeyState<'_>,
     encrypted_file_path: String,
-    password: String,
     output_path: String,
-) -> Result<(), String> {
-    crate::encryption::decrypt_file(&encrypted_file_path, &password, &output_path)
+) -> Result<(), VaultError> {
+    let key = current_master_key(&key_state).await?;
+    crate::encryption::decrypt_file(&encrypted_file_path, &key, &output_path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| VaultError::CorruptData {
+            path: encrypted_file_path,
+            detail: e.to_string(),
+        })
 }
 
 #[tauri::command]
 pub async fn initialize_vault(
-    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
     master_password: String,
-) -> Result<PythonBackendResponse, String> {
-    let mut args = HashMap::new();
-    args.insert(
-        "password".to_string(),
-        serde_json::Value::String(master_password),
-    );
+) -> Result<(), VaultError> {
+    let key = crate::keys::initialize_vault_key(db.inner().as_ref(), &master_password)
+        .await
+        .map_err(VaultError::from)?;
+    *key_state.0.write().await = Some(key);
 
-    call_python_backend(&app_handle, "initialize", &args).await
+    db.log_auth_event("vault_initialized", "success", None)
+        .await
+        .map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn unlock_vault(
-    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
     master_password: String,
-) -> Result<PythonBackendResponse, String> {
-    let mut args = HashMap::new();
-    args.insert(
-        "password".to_string(),
-        serde_json::Value::String(master_password),
-    );
+) -> Result<(), VaultError> {
+    match crate::keys::migrate_legacy_vault_key(db.inner().as_ref(), &master_password).await {
+        Ok(key) => {
+            *key_state.0.write().await = Some(key);
+            db.log_auth_event("vault_unlocked", "success", None)
+                .await
+                .map_err(VaultError::from)
+        }
+        Err(e) => {
+            let _ = db
+                .log_auth_event("vault_unlocked", "failure", Some(&e.to_string()))
+                .await;
+            Err(VaultError::from(e))
+        }
+    }
+}
 
-    call_python_backend(&app_handle, "unlock", &args).await
+/// Explicit, separate escape hatch for a legacy vault `unlock_vault` refused to adopt
+/// automatically (`VaultError::LegacyVaultUnverified`): adopts `master_password` going forward
+/// with no way to verify it against the vault's existing images, and flags every one of them
+/// `needs_reencryption` so the frontend can warn the user those files need to be re-saved before
+/// they're readable again. The frontend must only call this after the user has explicitly
+/// confirmed they understand that tradeoff — never as a silent fallback from `unlock_vault`.
+#[tauri::command]
+pub async fn adopt_legacy_vault_password(
+    db: Store<'_>,
+    key_state: KeyState<'_>,
+    master_password: String,
+) -> Result<(), VaultError> {
+    let key = crate::keys::adopt_legacy_vault_password(db.inner().as_ref(), &master_password)
+        .await
+        .map_err(VaultError::from)?;
+    *key_state.0.write().await = Some(key);
+
+    db.log_auth_event(
+        "legacy_vault_adopted",
+        "success",
+        Some("password adopted without verification; existing images flagged needs_reencryption"),
+    )
+    .await
+    .map_err(VaultError::from)
 }
 
 #[tauri::command]
-pub async fn lock_vault(app_handle: tauri::AppHandle) -> Result<PythonBackendResponse, String> {
-    let args = HashMap::new();
-    call_python_backend(&app_handle, "lock", &args).await
+pub async fn lock_vault(db: Store<'_>, key_state: KeyState<'_>) -> Result<(), VaultError> {
+    *key_state.0.write().await = None;
+    db.log_auth_event("vault_locked", "success", None)
+        .await
+        .map_err(VaultError::from)
+}
+
+#[tauri::command]
+pub async fn change_passphrase(
+    db: Store<'_>,
+    key_state: KeyState<'_>,
+    old_password: String,
+    new_password: String,
+) -> Result<(), VaultError> {
+    crate::keys::change_passphrase(db.inner().as_ref(), &old_password, &new_password)
+        .await
+        .map_err(VaultError::from)?;
+    *key_state.0.write().await = None;
+
+    db.log_auth_event("passphrase_changed", "success", None)
+        .await
+        .map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn add_image_from_frontend(
-    app_handle: tauri::AppHandle,
+    python: Python<'_>,
     file_contents: String, // Expecting base64 encoded file
     tags: Vec<String>,
     password: Option<String>, // Add optional password parameter
-) -> Result<PythonBackendResponse, String> {
+) -> Result<PythonBackendResponse, VaultError> {
     let mut args = HashMap::new();
     args.insert(
         "file_contents".to_string(),
@@ -389,32 +492,23 @@ pub async fn add_image_from_frontend(
         args.insert("password".to_string(), serde_json::Value::String(pwd));
     }
 
-    call_python_backend(&app_handle, "add_image", &args).await
+    call_python_backend(&python, "add_image", &args).await
 }
 
+/// Parse `query` as a structured search query (see [`crate::query`]) and run it against the
+/// vault's metadata, e.g. `tag:landscape AND (tag:2023 OR tag:2024) AND NOT tag:draft`. Runs
+/// entirely in SQLite rather than round-tripping through the Python backend.
 #[tauri::command]
-pub async fn search_images(
-    app_handle: tauri::AppHandle,
-    query: String,
-    tags: Vec<String>,
-) -> Result<PythonBackendResponse, String> {
-    let mut args = HashMap::new();
-    args.insert("query".to_string(), serde_json::Value::String(query));
-    args.insert(
-        "tags".to_string(),
-        serde_json::Value::String(
-            serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string()),
-        ),
-    );
-
-    call_python_backend(&app_handle, "search_images", &args).await
+pub async fn search_images(db: Store<'_>, query: String) -> Result<Vec<ImageRecord>, VaultError> {
+    let parsed = crate::query::parse(&query).map_err(VaultError::from)?;
+    db.search_images(&parsed).await.map_err(VaultError::from)
 }
 
 #[tauri::command]
 pub async fn get_decrypted_image(
-    app_handle: tauri::AppHandle,
+    python: Python<'_>,
     image_id: i64,
-) -> Result<PythonBackendResponse, String> {
+) -> Result<PythonBackendResponse, VaultError> {
     let mut args = HashMap::new();
     args.insert(
         "image-id".to_string(),
@@ -422,27 +516,106 @@ pub async fn get_decrypted_image(
     );
     args.insert("decrypt".to_string(), serde_json::Value::Bool(true));
 
-    call_python_backend(&app_handle, "get_image", &args).await
+    call_python_backend(&python, "get_image", &args).await
 }
 
+/// Return `image_id`'s cached thumbnail (see [`crate::thumbnail`]), regenerating it from the
+/// reassembled original only if it's missing or stale. Runs entirely in this process instead of
+/// round-tripping through the Python backend.
 #[tauri::command]
 pub async fn get_image_thumbnail(
     app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
     image_id: i64,
-) -> Result<PythonBackendResponse, String> {
-    let mut args = HashMap::new();
-    args.insert(
-        "image-id".to_string(),
-        serde_json::Value::String(image_id.to_string()),
-    );
+) -> Result<Vec<u8>, VaultError> {
+    let image = db
+        .get_image_by_id(image_id)
+        .await
+        .map_err(VaultError::from)?
+        .ok_or(VaultError::ImageNotFound(image_id))?;
 
-    call_python_backend(&app_handle, "get_image", &args).await
+    let key = current_master_key(&key_state).await?;
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+
+    if let Some(cached) = crate::thumbnail::read_cached(&vault_dir, &key, image_id, &image.file_hash)
+        .map_err(VaultError::from)?
+    {
+        return Ok(cached);
+    }
+
+    let plaintext = crate::chunking::reassemble_image(db.inner().as_ref(), &vault_dir, &key, image_id)
+        .await
+        .map_err(VaultError::from)?;
+
+    crate::thumbnail::render_and_cache(db.inner().as_ref(), &vault_dir, &key, image_id, &image.file_hash, &plaintext)
+        .await
+        .map_err(VaultError::from)
 }
 
 #[tauri::command]
-pub async fn get_vault_status(
-    app_handle: tauri::AppHandle,
-) -> Result<PythonBackendResponse, String> {
+pub async fn get_vault_status(python: Python<'_>) -> Result<PythonBackendResponse, VaultError> {
     let args = HashMap::new();
-    call_python_backend(&app_handle, "get_vault_status", &args).await
+    call_python_backend(&python, "get_vault_status", &args).await
+}
+
+fn chunk_store_vault_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, VaultError> {
+    Ok(app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| VaultError::Other(anyhow::anyhow!("Failed to get app data directory")))?
+        .join("vault"))
+}
+
+/// Split `file_path`'s contents into deduplicated, encrypted chunks under the vault's chunk
+/// store and record them against `image_id` (see [`crate::chunking`]).
+#[tauri::command]
+pub async fn import_image_chunked(
+    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
+    image_id: i64,
+    file_path: String,
+) -> Result<(), VaultError> {
+    let key = current_master_key(&key_state).await?;
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+    let plaintext = std::fs::read(&file_path).map_err(|e| VaultError::CorruptData {
+        path: file_path,
+        detail: e.to_string(),
+    })?;
+
+    crate::chunking::store_image_chunked(db.inner().as_ref(), &vault_dir, &key, image_id, &plaintext)
+        .await
+        .map_err(VaultError::from)
+}
+
+/// Reassemble `image_id` from the chunk store and return its decrypted bytes.
+#[tauri::command]
+pub async fn get_decrypted_image_chunked(
+    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    key_state: KeyState<'_>,
+    image_id: i64,
+) -> Result<Vec<u8>, VaultError> {
+    let key = current_master_key(&key_state).await?;
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+
+    crate::chunking::reassemble_image(db.inner().as_ref(), &vault_dir, &key, image_id)
+        .await
+        .map_err(VaultError::from)
+}
+
+/// Unlink `image_id` from its chunks and garbage-collect any chunk left with no other
+/// references.
+#[tauri::command]
+pub async fn delete_image_chunks(
+    app_handle: tauri::AppHandle,
+    db: Store<'_>,
+    image_id: i64,
+) -> Result<(), VaultError> {
+    let vault_dir = chunk_store_vault_dir(&app_handle)?;
+
+    crate::chunking::delete_image_chunks(db.inner().as_ref(), &vault_dir, image_id)
+        .await
+        .map_err(VaultError::from)
 }
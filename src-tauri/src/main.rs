@@ -2,11 +2,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
-mod database;
-mod encryption;
+mod python_worker;
+
+// The vault's storage backends, master-key scheme, chunk store, capability tokens, and file
+// encryption live in `graphivault-core` so `graphivault-cli` can share them; re-exported here so
+// `crate::database`, `crate::store`, etc. keep working exactly as before the extraction.
+pub use graphivault_core::{capability, chunking, database, encryption, keys, query, store, thumbnail};
 
-use sqlx::SqlitePool;
 use std::fs;
+use std::sync::Arc;
+use store::VaultStore;
 use tauri::{CustomMenuItem, Manager, SystemTray, SystemTrayMenu};
 
 fn main() {
@@ -23,26 +28,42 @@ fn main() {
             commands::init_database,
             commands::add_image,
             commands::get_images,
+            commands::list_images,
             commands::get_image_by_id,
             commands::get_image_by_hash,
             commands::delete_image,
             commands::add_tag,
             commands::get_image_tags,
+            commands::get_tag_history,
+            commands::restore_tag,
             commands::add_annotation,
             commands::get_image_annotations,
+            commands::register_image_variant,
+            commands::get_image_variants,
+            commands::get_image_variant,
+            commands::get_variant_presets,
             commands::set_vault_setting,
             commands::get_vault_setting,
             commands::get_vault_info,
+            commands::grant_capability,
+            commands::list_capabilities,
+            commands::revoke_capability,
+            commands::redeem_capability,
             commands::encrypt_file,
             commands::decrypt_file,
             commands::initialize_vault,
             commands::get_vault_status,
             commands::unlock_vault,
+            commands::adopt_legacy_vault_password,
             commands::lock_vault,
+            commands::change_passphrase,
             commands::add_image_from_frontend,
             commands::search_images,
             commands::get_decrypted_image,
-            commands::get_image_thumbnail
+            commands::get_image_thumbnail,
+            commands::import_image_chunked,
+            commands::get_decrypted_image_chunked,
+            commands::delete_image_chunks
         ])
         .setup(|app| {
             // Initialize database on startup and register pool as state
@@ -94,16 +115,31 @@ fn main() {
             let rt = tokio::runtime::Runtime::new()
                 .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
 
-            let pool = rt
-                .block_on(SqlitePool::connect(&database_url))
-                .map_err(|e| format!("Failed to connect to database: {}", e))?;
+            // Which backend to talk to is a vault-level setting, not a compile-time choice; for
+            // now only `sqlite` exists, but this is the seam Postgres/MySQL stores plug into.
+            let store: Arc<dyn VaultStore> = Arc::from(
+                rt.block_on(crate::store::select_store(&database_url, None))
+                    .map_err(|e| format!("Failed to connect to database: {}", e))?,
+            );
 
-            // Initialize the database schema
-            rt.block_on(crate::database::init_db(&pool))
+            // Apply the schema for whichever backend was selected
+            rt.block_on(store.migrate())
                 .map_err(|e| format!("Failed to initialize database: {}", e))?;
 
             println!("Database initialized successfully");
-            app.manage(pool);
+            app.manage(store);
+            app.manage(keys::VaultKeyState::default());
+
+            // One long-lived Python backend process, shared across every command instead of
+            // being spawned fresh per call; it is lazily started on first use and restarted
+            // automatically if it dies.
+            let script_path = python_worker::resolve_script_path(&app_handle)
+                .map_err(|e| format!("Failed to locate Python backend: {}", e))?;
+            app.manage(Arc::new(python_worker::PythonBackend::new(
+                script_path,
+                vault_dir.clone(),
+            )));
+
             Ok(())
         })
         .run(tauri::generate_context!())
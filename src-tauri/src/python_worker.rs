@@ -0,0 +1,188 @@
+//! Long-lived Python backend worker.
+//!
+//! `call_python_backend` used to spawn a fresh `python main.py ...` process for every single
+//! command, paying interpreter startup cost (and, once the vault is unlocked, having nothing to
+//! show for it between calls) every time. [`PythonBackend`] is created at `setup()` time and kept
+//! in Tauri state; it spawns one child process on the first `call` (not at `setup()`) and reuses
+//! it for every later call, talking to it over a newline-delimited
+//! JSON-RPC protocol on its stdin/stdout: one `{"id", "method", "params"}` object per line out,
+//! one `{"id", "result"}` / `{"id", "error"}` object per line back. A background reader task
+//! matches each response to its request by `id` and completes a oneshot channel, so concurrent
+//! callers never see each other's replies. If the child has exited, the next `call` restarts it.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::time::Duration;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Serialize)]
+struct RpcRequest<'a> {
+    id: u64,
+    method: &'a str,
+    params: &'a HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    id: u64,
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<RpcResponse>>>>;
+
+struct Worker {
+    child: Child,
+    stdin: ChildStdin,
+    pending: PendingMap,
+}
+
+/// Manages one long-lived `python <script_path> --serve --vault-path <vault_dir>` process,
+/// restarting it if it dies. `vault_dir` is passed at spawn time rather than per-call since the
+/// worker is pinned to a single vault for its whole lifetime, same as the Rust-side `VaultStore`.
+pub struct PythonBackend {
+    script_path: PathBuf,
+    vault_dir: PathBuf,
+    worker: Mutex<Option<Worker>>,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl PythonBackend {
+    pub fn new(script_path: PathBuf, vault_dir: PathBuf) -> Self {
+        Self {
+            script_path,
+            vault_dir,
+            worker: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        }
+    }
+
+    async fn spawn_worker(&self) -> Result<Worker> {
+        let mut child = Command::new("python")
+            .arg(&self.script_path)
+            .arg("--serve")
+            .arg("--vault-path")
+            .arg(&self.vault_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| anyhow!("failed to spawn Python backend: {}", e))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Python backend child has no stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Python backend child has no stdout"))?;
+
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let reader_pending = pending.clone();
+
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let Ok(response) = serde_json::from_str::<RpcResponse>(&line) else {
+                    continue;
+                };
+                if let Some(sender) = reader_pending.lock().await.remove(&response.id) {
+                    let _ = sender.send(response);
+                }
+            }
+        });
+
+        Ok(Worker {
+            child,
+            stdin,
+            pending,
+        })
+    }
+
+    /// Invoke `method` on the worker, spawning or restarting it first if it isn't running.
+    pub async fn call(
+        &self,
+        method: &str,
+        params: &HashMap<String, serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut line = serde_json::to_string(&RpcRequest { id, method, params })?;
+        line.push('\n');
+
+        let mut guard = self.worker.lock().await;
+
+        let needs_restart = match guard.as_mut() {
+            Some(worker) => worker.child.try_wait().ok().flatten().is_some(),
+            None => true,
+        };
+        if needs_restart {
+            *guard = Some(self.spawn_worker().await?);
+        }
+
+        let worker = guard.as_mut().expect("worker was just spawned");
+        let (tx, rx) = oneshot::channel();
+        worker.pending.lock().await.insert(id, tx);
+
+        if let Err(e) = worker.stdin.write_all(line.as_bytes()).await {
+            worker.pending.lock().await.remove(&id);
+            *guard = None; // force a fresh spawn on the next call
+            return Err(anyhow!("failed to write to Python backend: {}", e));
+        }
+
+        drop(guard);
+
+        let response = tokio::time::timeout(self.timeout, rx)
+            .await
+            .map_err(|_| anyhow!("Python backend call '{}' timed out", method))?
+            .map_err(|_| anyhow!("Python backend closed its stdout before answering '{}'", method))?;
+
+        match response.error {
+            Some(error) => Err(anyhow!("Python backend error: {}", error)),
+            None => response
+                .result
+                .ok_or_else(|| anyhow!("Python backend returned no result for '{}'", method)),
+        }
+    }
+}
+
+/// Locate `python_backend/main.py` relative to the project root in development, or the resource
+/// directory in a packaged build.
+pub fn resolve_script_path(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
+    let python_backend_dir = if cfg!(debug_assertions) {
+        let mut current_dir = std::env::current_dir()?;
+        loop {
+            if current_dir.join("package.json").exists() && current_dir.join("python_backend").exists() {
+                break current_dir.join("python_backend");
+            }
+            match current_dir.parent() {
+                Some(parent) => current_dir = parent.to_path_buf(),
+                None => return Err(anyhow!("could not locate python_backend/ from the project root")),
+            }
+        }
+    } else {
+        app_handle
+            .path_resolver()
+            .resource_dir()
+            .ok_or_else(|| anyhow!("could not resolve resource directory"))?
+            .join("python_backend")
+    };
+
+    Ok(python_backend_dir.join("main.py"))
+}